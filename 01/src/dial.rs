@@ -0,0 +1,69 @@
+/// Something noteworthy happening while the dial rotates
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum Event {
+    /// The dial pointed at 0 during a rotation, but kept going
+    Pass,
+    /// The dial came to rest at 0 at the end of a rotation
+    Land,
+}
+
+/// Combination lock dial with positions 0..size
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct Dial {
+    size: i64,
+    position: i64,
+}
+
+impl Dial {
+    pub fn new(size: i64, start: i64) -> Self {
+        assert!(size > 0);
+        Dial {
+            size,
+            position: start.rem_euclid(size),
+        }
+    }
+
+    pub fn size(&self) -> i64 {
+        self.size
+    }
+
+    pub fn position(&self) -> i64 {
+        self.position
+    }
+
+    /// Rotates the dial by `clicks` (negative is left) and returns every time
+    /// the dial pointed at 0 along the way.
+    pub fn step(&mut self, clicks: i64) -> impl Iterator<Item = Event> + use<> {
+        // Distance to the next 0 in the direction of travel (a full turn if
+        // we're starting at 0).
+        let to_zero = match (clicks < 0, self.position) {
+            (_, 0) => self.size,
+            (true, position) => position,
+            (false, position) => self.size - position,
+        };
+
+        let distance = clicks.abs();
+        let zeros = if distance < to_zero {
+            0
+        } else {
+            1 + (distance - to_zero) / self.size
+        };
+
+        self.position = (self.position + clicks).rem_euclid(self.size);
+        // Not moving at all while at 0 still counts as landing there
+        let landed = self.position == 0;
+        let passes = if landed { (zeros - 1).max(0) } else { zeros };
+
+        std::iter::repeat_n(Event::Pass, passes as usize).chain(landed.then_some(Event::Land))
+    }
+
+    /// Applies all rotations in order and returns the combined events
+    pub fn events<I>(mut self, rotations: I) -> impl Iterator<Item = Event>
+    where
+        I: IntoIterator<Item = i64>,
+    {
+        rotations
+            .into_iter()
+            .flat_map(move |clicks| self.step(clicks))
+    }
+}
@@ -1,42 +1,38 @@
 #![allow(dead_code)]
 
+mod dial;
+mod reverse;
+
+use dial::{Dial, Event};
 use std::io::Result;
 use std::path::Path;
 
-fn decode<P: AsRef<Path>>(path: P) -> Result<(i32, i32)> {
-    // PERFORMANCE(AE): I'm sure (***), combining map() calls would be faster here.
-    // But leaving individual map() calls here for clarity. Until performance becomes
-    // an issue ;).
-    let (zeros, turns, _) = std::fs::read_to_string(path)?
+fn parse_rotation(line: &str) -> i64 {
+    line[1..].parse::<i64>().unwrap() * if line.starts_with('L') { -1 } else { 1 }
+}
+
+fn format_rotation(clicks: i64) -> String {
+    format!("{}{}", if clicks < 0 { 'L' } else { 'R' }, clicks.abs())
+}
+
+fn read_rotations<P: AsRef<Path>>(path: P) -> Result<Vec<i64>> {
+    Ok(std::fs::read_to_string(path)?
         .lines()
-        // Extract number
-        .map(|line| line[1..].parse::<i32>().unwrap() * if line.starts_with('L') { -1 } else { 1 })
-        // Calculate full rotations and remaining steps
-        .map(|clicks| ((clicks / 100).abs(), clicks % 100))
-        // Apply the steps from a given starting position
-        .fold(
-            (0, 0, 50),
-            |(zeros, turns, old_position), (new_turns, clicks)| {
-                let position = old_position + clicks;
-
-                // This logic gets messy because it needs to prevent double counting of rotations
-                // when we either started at 0 or ended there...
-                let (new_position, extra_turn) = if position < 0 {
-                    (position + 100, if old_position != 0 { 1 } else { 0 })
-                } else if position > 99 {
-                    (position - 100, if position != 100 { 1 } else { 0 })
-                } else {
-                    (position, 0)
-                };
+        .map(parse_rotation)
+        .collect())
+}
 
-                (
-                    zeros + if new_position == 0 { 1 } else { 0 },
-                    turns + new_turns + extra_turn,
-                    new_position,
-                )
-            },
-        );
-    Ok((zeros, turns + zeros))
+// Returns the number of rotations ending at 0, and the number of times the
+// dial pointed at 0 in total.
+fn count_zeros(dial: Dial, rotations: &[i64]) -> (usize, usize) {
+    dial.events(rotations.iter().copied())
+        .fold((0, 0), |(zeros, clicks), event| {
+            (zeros + usize::from(event == Event::Land), clicks + 1)
+        })
+}
+
+fn decode<P: AsRef<Path>>(path: P) -> Result<(usize, usize)> {
+    Ok(count_zeros(Dial::new(100, 50), &read_rotations(path)?))
 }
 
 fn main() {
@@ -47,12 +43,105 @@ fn main() {
 mod tests {
     mod day01_secret_entrance {
         use super::super::*;
+        use utils::Rng;
 
         #[test]
         fn sample() {
             assert_eq!(decode("data/sample.txt").unwrap(), (3, 6));
         }
 
+        #[test]
+        fn dial_steps() {
+            let mut dial = Dial::new(100, 50);
+            assert_eq!(dial.step(-68).collect::<Vec<_>>(), vec![Event::Pass]);
+            assert_eq!(dial.position(), 82);
+            assert_eq!(dial.step(18).collect::<Vec<_>>(), vec![Event::Land]);
+
+            // Starting at 0 doesn't count as passing it again
+            assert_eq!(dial.step(-5).count(), 0);
+            assert_eq!(dial.position(), 95);
+
+            // Large rotations pass 0 once per full turn
+            let events: Vec<_> = dial.step(1005).collect();
+            assert_eq!(events.len(), 11);
+            assert_eq!(events.last(), Some(&Event::Land));
+            assert_eq!(dial.step(-1000).filter(|e| *e == Event::Pass).count(), 9);
+            assert_eq!(dial.position(), 0);
+
+            // Not moving at all while on 0 still counts as landing
+            assert_eq!(dial.step(0).collect::<Vec<_>>(), vec![Event::Land]);
+            dial.step(1).for_each(drop);
+            assert_eq!(dial.step(0).count(), 0);
+
+            let mut small = Dial::new(3, -1);
+            assert_eq!(small.position(), 2);
+            assert_eq!(small.step(-2).collect::<Vec<_>>(), vec![Event::Land]);
+        }
+
+        // Independent click-by-click simulation to check against
+        fn brute_force(size: i64, start: i64, rotations: &[i64]) -> (usize, usize) {
+            let (mut position, mut zeros, mut clicks) = (start, 0, 0);
+            for rotation in rotations {
+                for _ in 0..rotation.abs() {
+                    position = (position + rotation.signum()).rem_euclid(size);
+                    clicks += usize::from(position == 0);
+                }
+                zeros += usize::from(position == 0);
+            }
+            (zeros, clicks)
+        }
+
+        #[test]
+        fn reverse() {
+            let dial = Dial::new(100, 50);
+            let rotations = reverse::solve((3, 3), dial, 10).unwrap();
+            let lines: Vec<String> = rotations.iter().map(|r| format_rotation(*r)).collect();
+            let parsed: Vec<i64> = lines.iter().map(|line| parse_rotation(line)).collect();
+            assert_eq!(count_zeros(dial, &parsed), (3, 6));
+
+            assert_eq!(reverse::solve((2, 1), Dial::new(1, 0), 2), Some(vec![2, 1]));
+            assert_eq!(reverse::solve((0, 0), Dial::new(1, 0), 2), None);
+            assert_eq!(reverse::solve((0, 1), dial, 0), None);
+            assert_eq!(reverse::solve((4, 0), dial, 3), None);
+        }
+
+        #[test]
+        fn reverse_fuzz() {
+            let mut rng = Rng::new(2025);
+            for _ in 0..500 {
+                let size = rng.range(1..12) as i64;
+                let start = rng.range(0..size as u64) as i64;
+                let length = rng.index(10);
+                let zeros = rng.index(length + 1);
+                let passes = rng.index(25);
+
+                let dial = Dial::new(size, start);
+                let Some(rotations) = reverse::random((zeros, passes), dial, length, &mut rng)
+                else {
+                    assert!(reverse::solve((zeros, passes), dial, length).is_none());
+                    continue;
+                };
+                assert_eq!(rotations.len(), length);
+                assert_eq!(count_zeros(dial, &rotations), (zeros, zeros + passes));
+                assert_eq!(
+                    count_zeros(dial, &rotations),
+                    brute_force(size, start, &rotations)
+                );
+            }
+
+            for _ in 0..200 {
+                let size = rng.range(2..150) as i64;
+                let dial = Dial::new(size, rng.range(0..size as u64) as i64);
+                let (length, zeros, passes) = (20, rng.index(21), rng.index(100));
+                let rotations = reverse::random((zeros, passes), dial, length, &mut rng).unwrap();
+                assert_eq!(count_zeros(dial, &rotations), (zeros, zeros + passes));
+                assert_eq!(
+                    brute_force(size, dial.position(), &rotations),
+                    (zeros, zeros + passes)
+                );
+            }
+        }
+
         #[test]
         #[cfg(feature = "private")]
         fn input() {
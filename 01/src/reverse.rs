@@ -0,0 +1,125 @@
+// Going backwards: find rotations that produce a given number of landings on
+// and passes over 0. Every rotation is built in two parts:
+//   1) A base move that either lands on 0, or stops somewhere else without
+//      crossing 0 along the way.
+//   2) Any number of extra full turns, each of which adds exactly one pass.
+
+use crate::dial::Dial;
+use utils::Rng;
+
+#[derive(Debug, Copy, Clone)]
+struct Plan {
+    lands: bool,
+    left: bool,
+    turns: i64,
+    // Where to stop, if the rotation doesn't land on 0
+    stop: i64,
+}
+
+fn is_possible((zeros, passes): (usize, usize), size: i64, length: usize) -> bool {
+    // A dial of size 1 is always at 0, so every rotation lands
+    if size == 1 {
+        zeros == length && (passes == 0 || length > 0)
+    } else {
+        zeros <= length && (passes == 0 || length > 0)
+    }
+}
+
+fn rotations_from_plan(dial: Dial, plans: &[Plan]) -> Vec<i64> {
+    let size = dial.size();
+    let mut position = dial.position();
+
+    plans
+        .iter()
+        .map(|plan| {
+            let (base, left) = if plan.lands {
+                match position {
+                    0 => (size, plan.left),
+                    _ if plan.left => (position, true),
+                    _ => (size - position, false),
+                }
+            } else if plan.stop == position {
+                (0, plan.left)
+            } else if position == 0 {
+                if plan.left {
+                    (size - plan.stop, true)
+                } else {
+                    (plan.stop, false)
+                }
+            } else if plan.stop > position {
+                (plan.stop - position, false)
+            } else {
+                (position - plan.stop, true)
+            };
+
+            position = if plan.lands { 0 } else { plan.stop };
+            let clicks = base + plan.turns * size;
+            if left { -clicks } else { clicks }
+        })
+        .collect()
+}
+
+/// Finds `length` rotations for `dial` that land on 0 `zeros` times and pass
+/// it `passes` times. Returns None if that's impossible.
+pub fn solve(target: (usize, usize), dial: Dial, length: usize) -> Option<Vec<i64>> {
+    let (zeros, passes) = target;
+    if !is_possible(target, dial.size(), length) {
+        return None;
+    }
+
+    let mut plans: Vec<Plan> = (0..length)
+        .map(|idx| Plan {
+            lands: idx < zeros,
+            left: false,
+            turns: 0,
+            stop: 1,
+        })
+        .collect();
+
+    if let Some(plan) = plans.first_mut() {
+        plan.turns = passes as i64;
+    }
+
+    Some(rotations_from_plan(dial, &plans))
+}
+
+/// Same as solve(), but with randomized directions, stops and landing order,
+/// and the passes spread randomly over the rotations.
+pub fn random(
+    target: (usize, usize),
+    dial: Dial,
+    length: usize,
+    rng: &mut Rng,
+) -> Option<Vec<i64>> {
+    let (zeros, passes) = target;
+    let size = dial.size();
+    if !is_possible(target, size, length) {
+        return None;
+    }
+
+    let mut plans: Vec<Plan> = (0..length)
+        .map(|idx| Plan {
+            lands: idx < zeros,
+            left: rng.bool(),
+            turns: 0,
+            stop: if size > 1 {
+                rng.range(1..size as u64) as i64
+            } else {
+                1
+            },
+        })
+        .collect();
+    rng.shuffle(&mut plans);
+
+    // Split the passes at random cut points between all rotations
+    let mut cuts: Vec<u64> = (1..length)
+        .map(|_| rng.range(0..passes as u64 + 1))
+        .chain([0, passes as u64])
+        .collect();
+    cuts.sort();
+    for (plan, cut) in plans.iter_mut().zip(cuts.windows(2)) {
+        plan.turns = (cut[1] - cut[0]) as i64;
+    }
+
+    Some(rotations_from_plan(dial, &plans))
+}
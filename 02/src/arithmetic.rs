@@ -0,0 +1,53 @@
+// Instead of testing every number in a range, the repeated-pattern numbers are
+// enumerated directly. Every `len` digit number made of a `period` digit block
+// is `block * multiplier`, where the multiplier is 1 0..01 0..01 (for example
+// 123123123 = 123 * 1001001). So for a fixed length and period, the candidates
+// form an arithmetic progression and their sum can be calculated in O(1).
+
+// u64::MAX has 20 digits
+const MAX_DIGITS: u32 = 20;
+
+fn divisors(n: u32) -> impl Iterator<Item = u32> {
+    (1..n).filter(move |d| n.is_multiple_of(*d))
+}
+
+// Sum of all `len` digit numbers within from..=to, that consist of a `period`
+// digit block repeated `len / period` times.
+fn periodic_sum(from: u128, to: u128, len: u32, period: u32) -> u128 {
+    let multiplier = (10u128.pow(len) - 1) / (10u128.pow(period) - 1);
+    let lo = std::cmp::max(10u128.pow(period - 1), from.div_ceil(multiplier));
+    let hi = std::cmp::min(10u128.pow(period) - 1, to / multiplier);
+
+    if lo > hi {
+        0
+    } else {
+        // (lo + hi) * count is always even
+        multiplier * ((lo + hi) * (hi - lo + 1) / 2)
+    }
+}
+
+// Same as periodic_sum(), but only for numbers where `period` is the shortest
+// repeating block. Numbers with a shorter period (a divisor of `period`) are
+// subtracted, so they're not counted more than once.
+fn primitive_sum(from: u128, to: u128, len: u32, period: u32) -> u128 {
+    periodic_sum(from, to, len, period)
+        - divisors(period)
+            .map(|d| primitive_sum(from, to, len, d))
+            .sum::<u128>()
+}
+
+/// Arithmetic equivalent of filtering from..=to with is_repeat_twice()
+pub fn repeat_twice_sum(from: u64, to: u64) -> u128 {
+    (2..=MAX_DIGITS)
+        .step_by(2)
+        .map(|len| periodic_sum(from.into(), to.into(), len, len / 2))
+        .sum()
+}
+
+/// Arithmetic equivalent of filtering from..=to with is_repeat_n()
+pub fn repeat_n_sum(from: u64, to: u64) -> u128 {
+    (2..=MAX_DIGITS)
+        .flat_map(|len| divisors(len).map(move |period| (len, period)))
+        .map(|(len, period)| primitive_sum(from.into(), to.into(), len, period))
+        .sum()
+}
@@ -1,52 +1,84 @@
 #![allow(dead_code)]
 
+mod arithmetic;
+mod repetition;
+
+use repetition::{Pattern, Repetition};
+use std::io::{Error, ErrorKind};
+
 use std::io::Result;
 use std::path::Path;
 
 fn is_repeat_twice(n: &u64) -> bool {
-    let ns = n.to_string();
-    if ns.len().is_multiple_of(2) {
-        let (aa, bb) = ns.split_at(ns.len() / 2);
-        if aa == bb {
-            return true;
-        }
-    }
-    false
+    Pattern::decimal(Repetition::Exactly(2)).matches(*n)
 }
 
 fn is_repeat_n(n: &u64) -> bool {
-    let ns = n.to_string();
-    let len = ns.len();
-
-    (1..len / 2 + 1)
-        .rev()
-        .filter(|n| len.is_multiple_of(*n))
-        .map(|n| ns[..n].repeat(len / n))
-        .any(|part| part == ns)
+    Pattern::decimal(Repetition::AtLeast(2)).matches(*n)
 }
 
-fn invalid_id_sum<P: AsRef<Path>>(filename: P, filter_predicate: fn(&u64) -> bool) -> Result<u64> {
+fn read_ranges<P: AsRef<Path>>(filename: P) -> Result<Vec<(u64, u64)>> {
     Ok(std::fs::read_to_string(filename)?
         .lines()
         .next()
         .unwrap()
         .split(',')
-        .flat_map(|product_id| {
+        .map(|product_id| {
             let (a, b) = product_id.split_once('-').unwrap();
-            let na: u64 = a.parse().unwrap();
-            let nb: u64 = b.parse().unwrap();
-            (na..nb + 1).filter(filter_predicate)
+            (a.parse().unwrap(), b.parse().unwrap())
         })
+        .collect())
+}
+
+fn invalid_id_sum<P, F>(filename: P, filter_predicate: F) -> Result<u64>
+where
+    P: AsRef<Path>,
+    F: Fn(&u64) -> bool + Copy,
+{
+    Ok(read_ranges(filename)?
+        .into_iter()
+        .flat_map(|(na, nb)| (na..nb + 1).filter(filter_predicate))
         .sum())
 }
 
-fn main() {
-    println!("Run 'cargo test' instead ;)");
+// Same as invalid_id_sum(), but without visiting every number in the ranges.
+fn invalid_id_sum_arithmetic<P: AsRef<Path>>(
+    filename: P,
+    range_sum: fn(u64, u64) -> u128,
+) -> Result<u128> {
+    Ok(read_ranges(filename)?
+        .into_iter()
+        .map(|(na, nb)| range_sum(na, nb))
+        .sum())
+}
+
+fn main() -> Result<()> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.len() < 2 {
+        println!("Run 'cargo test' instead ;)");
+        println!("Or: day_02 <file> <exactly:K|at-least:K|period:P> [base]");
+        return Ok(());
+    }
+
+    let repetition: Repetition = args[1].parse()?;
+    let base = match args.get(2) {
+        Some(base) => base
+            .parse::<u64>()
+            .ok()
+            .filter(|base| *base >= 2)
+            .ok_or_else(|| Error::new(ErrorKind::InvalidInput, format!("invalid base '{base}'")))?,
+        None => 10,
+    };
+
+    let pattern = Pattern::new(repetition, base);
+    println!("{}", invalid_id_sum(&args[0], |n| pattern.matches(*n))?);
+    Ok(())
 }
 
 #[cfg(test)]
 mod tests {
     mod day02_gift_shop {
+        use super::super::arithmetic::*;
         use super::super::*;
 
         #[test]
@@ -61,6 +93,81 @@ mod tests {
             );
         }
 
+        #[test]
+        fn patterns() {
+            let matches = |repetition, base, n| Pattern::new(repetition, base).matches(n);
+
+            assert!(matches(Repetition::Exactly(2), 10, 1111));
+            assert!(matches(Repetition::Exactly(4), 10, 1111));
+            assert!(!matches(Repetition::Exactly(3), 10, 1111));
+            assert!(matches(Repetition::Exactly(3), 10, 121212));
+            assert!(!matches(Repetition::Exactly(2), 10, 121212));
+
+            assert!(matches(Repetition::AtLeast(3), 10, 121212));
+            assert!(!matches(Repetition::AtLeast(4), 10, 121212));
+            assert!(!matches(Repetition::AtLeast(2), 10, 7));
+
+            assert!(matches(Repetition::PrimitivePeriod(2), 10, 121212));
+            assert!(!matches(Repetition::PrimitivePeriod(2), 10, 1111));
+            assert!(!matches(Repetition::PrimitivePeriod(2), 10, 12));
+
+            // 0b101101 and 0xABAB
+            assert!(matches(Repetition::Exactly(2), 2, 45));
+            assert!(matches(Repetition::Exactly(2), 16, 0xabab));
+            assert!(!matches(Repetition::Exactly(2), 10, 0xabab));
+
+            assert_eq!(
+                "at-least:2".parse::<Repetition>().unwrap(),
+                Repetition::AtLeast(2)
+            );
+            assert!("at-least:0".parse::<Repetition>().is_err());
+            assert!("twice".parse::<Repetition>().is_err());
+        }
+
+        #[test]
+        fn sample_period() {
+            // Primitive periods partition the "at least twice" IDs
+            let total: u64 = (1..=5)
+                .map(|p| {
+                    let pattern = Pattern::decimal(Repetition::PrimitivePeriod(p));
+                    invalid_id_sum("data/sample.txt", |n| pattern.matches(*n)).unwrap()
+                })
+                .sum();
+            assert_eq!(total, 4174379265);
+        }
+
+        #[test]
+        fn sample_arithmetic() {
+            assert_eq!(
+                invalid_id_sum_arithmetic("data/sample.txt", repeat_twice_sum).unwrap(),
+                1227775554
+            );
+            assert_eq!(
+                invalid_id_sum_arithmetic("data/sample.txt", repeat_n_sum).unwrap(),
+                4174379265
+            );
+        }
+
+        #[test]
+        fn arithmetic_matches_brute_force() {
+            for (from, to) in [(0, 0), (1, 9), (0, 150_000), (987_654, 1_234_567)] {
+                let brute_twice: u64 = (from..=to).filter(is_repeat_twice).sum();
+                let brute_n: u64 = (from..=to).filter(is_repeat_n).sum();
+                assert_eq!(repeat_twice_sum(from, to), brute_twice.into());
+                assert_eq!(repeat_n_sum(from, to), brute_n.into());
+            }
+
+            // Close to u64::MAX, "1844674407" repeated twice is still in range
+            let (from, to) = (18_446_744_071_844_674_000, 18_446_744_071_844_675_000);
+            let brute_n: u128 = (from..=to).filter(is_repeat_n).map(u128::from).sum();
+            assert_eq!(brute_n, 18_446_744_071_844_674_407);
+            assert_eq!(repeat_twice_sum(from, to), brute_n);
+            assert_eq!(repeat_n_sum(from, to), brute_n);
+
+            // The full range must not overflow
+            assert!(repeat_twice_sum(0, u64::MAX) < repeat_n_sum(0, u64::MAX));
+        }
+
         #[test]
         #[cfg(feature = "private")]
         fn input() {
@@ -72,6 +179,14 @@ mod tests {
                 invalid_id_sum("data/input.txt", is_repeat_n).unwrap(),
                 43_287_141_963
             );
+            assert_eq!(
+                invalid_id_sum_arithmetic("data/input.txt", repeat_twice_sum).unwrap(),
+                34_826_702_005
+            );
+            assert_eq!(
+                invalid_id_sum_arithmetic("data/input.txt", repeat_n_sum).unwrap(),
+                43_287_141_963
+            );
         }
     }
 }
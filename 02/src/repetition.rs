@@ -0,0 +1,89 @@
+use std::io::{Error, ErrorKind};
+use std::str::FromStr;
+
+/// How a product ID has to repeat to be considered invalid
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum Repetition {
+    /// A digit block repeated exactly k times (1111 is "11" twice)
+    Exactly(usize),
+    /// A digit block repeated k or more times
+    AtLeast(usize),
+    /// The shortest repeating digit block is p digits long (and repeats)
+    PrimitivePeriod(usize),
+}
+
+/// Repetition rule applied to the digits of a number in a given base
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct Pattern {
+    pub repetition: Repetition,
+    pub base: u64,
+}
+
+// Most significant digit first
+fn digits(mut n: u64, base: u64) -> Vec<u64> {
+    let mut digits = vec![n % base];
+    while n >= base {
+        n /= base;
+        digits.push(n % base);
+    }
+    digits.reverse();
+    digits
+}
+
+fn has_period(digits: &[u64], period: usize) -> bool {
+    digits.len().is_multiple_of(period)
+        && digits.iter().skip(period).zip(digits).all(|(a, b)| a == b)
+}
+
+// Length of the shortest digit block that makes up the whole number
+fn primitive_period(digits: &[u64]) -> usize {
+    (1..digits.len())
+        .find(|&period| has_period(digits, period))
+        .unwrap_or(digits.len())
+}
+
+impl Pattern {
+    pub fn new(repetition: Repetition, base: u64) -> Self {
+        assert!(base >= 2);
+        Pattern { repetition, base }
+    }
+
+    pub fn decimal(repetition: Repetition) -> Self {
+        Self::new(repetition, 10)
+    }
+
+    pub fn matches(&self, n: u64) -> bool {
+        let digits = digits(n, self.base);
+        let len = digits.len();
+        let period = primitive_period(&digits);
+
+        match self.repetition {
+            Repetition::Exactly(k) => {
+                k > 0 && len.is_multiple_of(k) && has_period(&digits, len / k)
+            }
+            Repetition::AtLeast(k) => len / period >= k,
+            Repetition::PrimitivePeriod(p) => period == p && period < len,
+        }
+    }
+}
+
+impl FromStr for Repetition {
+    type Err = Error;
+
+    /// Parses "exactly:K", "at-least:K" or "period:P"
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || Error::new(ErrorKind::InvalidInput, format!("invalid repetition '{s}'"));
+        let (kind, count) = s.split_once(':').ok_or_else(invalid)?;
+        let count: usize = count.parse().map_err(|_| invalid())?;
+        if count == 0 {
+            return Err(invalid());
+        }
+
+        match kind {
+            "exactly" => Ok(Repetition::Exactly(count)),
+            "at-least" => Ok(Repetition::AtLeast(count)),
+            "period" => Ok(Repetition::PrimitivePeriod(count)),
+            _ => Err(invalid()),
+        }
+    }
+}
@@ -1,6 +1,10 @@
 #![allow(dead_code)]
 
-use std::io::Result;
+mod selection;
+
+use selection::{Mode, Selection, select};
+
+use std::io::{Error, ErrorKind, Result};
 use std::path::Path;
 
 fn to_u64(chr: char) -> u64 {
@@ -33,8 +37,64 @@ fn bms<P: AsRef<Path>>(filename: P, take: usize) -> Result<u64> {
         .sum())
 }
 
-fn main() {
-    println!("Run 'cargo test' instead ;)");
+fn select_banks<P: AsRef<Path>>(filename: P, take: usize, mode: Mode) -> Result<Vec<Selection>> {
+    std::fs::read_to_string(filename)?
+        .lines()
+        .map(|bank| {
+            let bank = selection::to_digits(bank);
+            if take == 0 || take > bank.len() {
+                return None;
+            }
+            select(&bank, take, mode)
+        })
+        .enumerate()
+        .map(|(line, picked)| {
+            picked.ok_or_else(|| {
+                Error::new(
+                    ErrorKind::InvalidData,
+                    format!("no valid selection for bank {}", line + 1),
+                )
+            })
+        })
+        .collect()
+}
+
+fn total_joltage(selections: &[Selection]) -> Result<u128> {
+    selections
+        .iter()
+        .try_fold(0u128, |sum, picked| sum.checked_add(picked.value()?))
+        .ok_or_else(|| Error::new(ErrorKind::InvalidData, "joltage overflow"))
+}
+
+// Same as bms(), but with the O(n) selection and u128 results
+fn bms_u128<P: AsRef<Path>>(filename: P, take: usize) -> Result<u128> {
+    total_joltage(&select_banks(filename, take, Mode::Largest)?)
+}
+
+fn main() -> Result<()> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.len() < 2 {
+        println!("Run 'cargo test' instead ;)");
+        println!("Or: day_03 <file> <take> [largest|smallest|window:W|gap:G] [report]");
+        return Ok(());
+    }
+
+    let take: usize = args[1]
+        .parse()
+        .map_err(|_| Error::new(ErrorKind::InvalidInput, "invalid battery count"))?;
+    let mode: Mode = match args.get(2) {
+        Some(mode) => mode.parse()?,
+        None => Mode::Largest,
+    };
+
+    let selections = select_banks(&args[0], take, mode)?;
+    if args.get(3).is_some_and(|arg| arg == "report") {
+        for (line, picked) in selections.iter().enumerate() {
+            println!("{:4}: {:?} {}", line + 1, picked.indices, picked);
+        }
+    }
+    println!("{}", total_joltage(&selections)?);
+    Ok(())
 }
 
 #[cfg(test)]
@@ -48,11 +108,104 @@ mod tests {
             assert_eq!(bms("data/sample.txt", 12).unwrap(), 3121910778619);
         }
 
+        #[test]
+        fn sample_u128() {
+            assert_eq!(bms_u128("data/sample.txt", 2).unwrap(), 357);
+            assert_eq!(bms_u128("data/sample.txt", 12).unwrap(), 3121910778619);
+            assert_eq!(
+                bms_u128("data/sample.txt", 15).unwrap(),
+                bms("data/sample.txt", 15).unwrap().into()
+            );
+
+            let bank = "818181911112111";
+            let picked = selection::largest(&selection::to_digits(bank), 12);
+            assert_eq!(picked.to_string(), "888911112111");
+            assert_eq!(
+                picked.indices,
+                vec![0, 2, 4, 6, 7, 8, 9, 10, 11, 12, 13, 14]
+            );
+
+            // Ties go to the leftmost battery
+            let picked = selection::largest(&selection::to_digits("9919"), 2);
+            assert_eq!(picked.indices, vec![0, 1]);
+
+            // More digits than fit into a u64 (or a u128)
+            let bank = "12345678909876543210".repeat(3);
+            let picked = selection::largest(&selection::to_digits(&bank), 30);
+            assert_eq!(picked.to_string(), "999987654322345678909876543210");
+            assert_eq!(picked.value(), Some(999987654322345678909876543210));
+            let all = selection::largest(&selection::to_digits(&bank), 60);
+            assert_eq!(all.to_string(), bank);
+            assert_eq!(all.value(), None);
+        }
+
+        #[test]
+        fn modes() {
+            let picked = |bank: &str, take, mode| {
+                select(&selection::to_digits(bank), take, mode)
+                    .map(|picked| (picked.to_string(), picked.indices))
+            };
+
+            assert_eq!(
+                picked("3041", 2, Mode::Smallest),
+                Some(("30".into(), vec![0, 1]))
+            );
+            assert_eq!(
+                picked("3041", 3, Mode::Smallest),
+                Some(("301".into(), vec![0, 1, 3]))
+            );
+            assert_eq!(
+                picked("9051", 2, Mode::Smallest),
+                Some(("51".into(), vec![2, 3]))
+            );
+            assert_eq!(picked("0009", 2, Mode::Smallest), None);
+
+            assert_eq!(
+                picked("9118911", 2, Mode::Window(3)),
+                Some(("91".into(), vec![0, 1]))
+            );
+            assert_eq!(
+                picked("1198911", 2, Mode::Window(3)),
+                Some(("99".into(), vec![2, 4]))
+            );
+            assert_eq!(picked("91", 2, Mode::Window(3)), None);
+
+            assert_eq!(
+                picked("9981", 2, Mode::Gap(2)),
+                Some(("98".into(), vec![0, 2]))
+            );
+            assert_eq!(
+                picked("9981", 2, Mode::Gap(3)),
+                Some(("91".into(), vec![0, 3]))
+            );
+            assert_eq!(picked("9981", 2, Mode::Gap(4)), None);
+
+            assert_eq!("window:4".parse::<Mode>().unwrap(), Mode::Window(4));
+            assert!("gap:0".parse::<Mode>().is_err());
+            assert!("biggest".parse::<Mode>().is_err());
+        }
+
+        #[test]
+        fn sample_modes() {
+            for take in 1..=15 {
+                let largest = select_banks("data/sample.txt", take, Mode::Largest).unwrap();
+                let gap = select_banks("data/sample.txt", take, Mode::Gap(1)).unwrap();
+                let window = select_banks("data/sample.txt", take, Mode::Window(15)).unwrap();
+                assert_eq!(largest, gap);
+                assert_eq!(largest, window);
+            }
+
+            let smallest = select_banks("data/sample.txt", 2, Mode::Smallest).unwrap();
+            assert_eq!(total_joltage(&smallest).unwrap(), 11 + 11 + 22 + 11);
+            assert_eq!(smallest[2].indices, vec![0, 3]);
+        }
+
         #[test]
         #[cfg(feature = "private")]
         fn input() {
             assert_eq!(bms("data/input.txt", 2).unwrap(), 16973);
             assert_eq!(bms("data/input.txt", 12).unwrap(), 168027167146027);
+            assert_eq!(bms_u128("data/input.txt", 12).unwrap(), 168027167146027);
         }
     }
 }
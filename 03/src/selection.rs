@@ -0,0 +1,170 @@
+use std::io::{Error, ErrorKind};
+use std::str::FromStr;
+
+/// Which batteries to pick from every bank
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum Mode {
+    /// Largest joltage (the puzzle)
+    Largest,
+    /// Smallest joltage without a leading 0
+    Smallest,
+    /// Largest joltage from a single window of consecutive batteries
+    Window(usize),
+    /// Largest joltage with picked batteries at least this far apart
+    Gap(usize),
+}
+
+/// Batteries picked from a bank, in bank order
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct Selection {
+    pub indices: Vec<usize>,
+    pub digits: Vec<u8>,
+}
+
+impl Selection {
+    fn from_indices(bank: &[u8], indices: Vec<usize>) -> Self {
+        let digits = indices.iter().map(|&idx| bank[idx]).collect();
+        Selection { indices, digits }
+    }
+
+    /// Joltage as a number, or None if it doesn't fit into a u128 (39+ digits)
+    pub fn value(&self) -> Option<u128> {
+        self.digits.iter().try_fold(0u128, |acc, &digit| {
+            acc.checked_mul(10)?.checked_add(digit.into())
+        })
+    }
+}
+
+impl std::fmt::Display for Selection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.digits
+            .iter()
+            .try_for_each(|digit| write!(f, "{digit}"))
+    }
+}
+
+pub fn to_digits(bank: &str) -> Vec<u8> {
+    bank.bytes().map(|chr| chr - b'0').collect()
+}
+
+/// Picks the largest `take` digit number from the bank in O(n), using a
+/// monotonic stack. A digit replaces smaller digits before it, as long as
+/// there are enough digits left to still pick `take` in total.
+/// Equal digits are never replaced, so ties go to the leftmost battery.
+pub fn largest(bank: &[u8], take: usize) -> Selection {
+    assert!(take <= bank.len());
+    let mut drops = bank.len() - take;
+    let mut stack: Vec<usize> = Vec::with_capacity(bank.len());
+
+    for (idx, digit) in bank.iter().enumerate() {
+        while drops > 0 && stack.last().is_some_and(|&top| bank[top] < *digit) {
+            stack.pop();
+            drops -= 1;
+        }
+        stack.push(idx);
+    }
+
+    stack.truncate(take);
+    Selection::from_indices(bank, stack)
+}
+
+/// Picks the smallest `take` digit number that doesn't start with a 0.
+/// Same idea as largest(), after picking the first digit separately.
+pub fn smallest(bank: &[u8], take: usize) -> Option<Selection> {
+    assert!(take > 0 && take <= bank.len());
+    let first = (0..=bank.len() - take)
+        .filter(|&idx| bank[idx] != 0)
+        .min_by_key(|&idx| bank[idx])?;
+
+    let rest = &bank[first + 1..];
+    let mut drops = rest.len() - (take - 1);
+    let mut stack: Vec<usize> = Vec::with_capacity(rest.len());
+
+    for (idx, digit) in rest.iter().enumerate() {
+        while drops > 0 && stack.last().is_some_and(|&top| rest[top] > *digit) {
+            stack.pop();
+            drops -= 1;
+        }
+        stack.push(idx);
+    }
+
+    stack.truncate(take - 1);
+    let indices = std::iter::once(first)
+        .chain(stack.into_iter().map(|idx| idx + first + 1))
+        .collect();
+    Some(Selection::from_indices(bank, indices))
+}
+
+/// Largest number with all batteries picked from a single run of `width`
+/// consecutive batteries. Ties go to the leftmost window.
+pub fn within_window(bank: &[u8], take: usize, width: usize) -> Option<Selection> {
+    if take > width || width > bank.len() {
+        return None;
+    }
+
+    (0..=bank.len() - width)
+        .map(|start| {
+            let mut picked = largest(&bank[start..start + width], take);
+            picked.indices.iter_mut().for_each(|idx| *idx += start);
+            picked
+        })
+        .rev()
+        .max_by(|a, b| a.digits.cmp(&b.digits))
+}
+
+/// Largest number where picked batteries are at least `gap` positions apart
+/// (a gap of 1 is the same as largest()). Greedily picks the leftmost largest
+/// digit that still leaves room for the remaining picks, so O(n * take).
+pub fn with_gap(bank: &[u8], take: usize, gap: usize) -> Option<Selection> {
+    assert!(take > 0 && gap > 0);
+    let span = (take - 1) * gap;
+    if span >= bank.len() {
+        return None;
+    }
+
+    let mut indices: Vec<usize> = Vec::with_capacity(take);
+    let mut from = 0;
+    for remaining in (0..take).rev() {
+        let last = bank.len() - 1 - remaining * gap;
+        let idx = (from..=last).rev().max_by_key(|&idx| bank[idx]).unwrap();
+        indices.push(idx);
+        from = idx + gap;
+    }
+
+    Some(Selection::from_indices(bank, indices))
+}
+
+/// Picks `take` batteries from `bank`, or None if the mode's constraints can't
+/// be met for this bank.
+pub fn select(bank: &[u8], take: usize, mode: Mode) -> Option<Selection> {
+    match mode {
+        Mode::Largest => Some(largest(bank, take)),
+        Mode::Smallest => smallest(bank, take),
+        Mode::Window(width) => within_window(bank, take, width),
+        Mode::Gap(gap) => with_gap(bank, take, gap),
+    }
+}
+
+impl FromStr for Mode {
+    type Err = Error;
+
+    /// Parses "largest", "smallest", "window:W" or "gap:G"
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || Error::new(ErrorKind::InvalidInput, format!("invalid mode '{s}'"));
+        let parameter = |value: &str| {
+            value
+                .parse::<usize>()
+                .ok()
+                .filter(|value| *value > 0)
+                .ok_or_else(invalid)
+        };
+
+        match s.split_once(':') {
+            None if s == "largest" => Ok(Mode::Largest),
+            None if s == "smallest" => Ok(Mode::Smallest),
+            Some(("window", width)) => Ok(Mode::Window(parameter(width)?)),
+            Some(("gap", gap)) => Ok(Mode::Gap(parameter(gap)?)),
+            _ => Err(invalid()),
+        }
+    }
+}
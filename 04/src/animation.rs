@@ -0,0 +1,148 @@
+use crate::erosion::Erosion;
+use std::io::{Error, ErrorKind, Result, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::Duration;
+use utils::Grid;
+
+const EMPTY: char = '.';
+const REMOVED: char = 'x';
+
+/// How to show the erosion
+#[derive(Debug, PartialEq, Clone)]
+pub enum Output {
+    /// Only the numbers
+    Stats,
+    /// Play back all frames in the terminal
+    Ansi(Duration),
+    /// Write every frame as a binary PPM image into a directory
+    Netpbm(PathBuf),
+    /// Single grid showing the round each roll was removed in
+    Summary,
+}
+
+/// Grid after every round, starting with the unchanged grid. Rolls removed in
+/// a round are marked with an 'x' in that round's frame.
+pub fn frames(grid: &Grid<char>, erosion: &Erosion) -> Vec<Grid<char>> {
+    let mut frame = grid.clone();
+    let mut frames = vec![frame.clone()];
+    for round in &erosion.rounds {
+        round.iter().for_each(|&(x, y)| {
+            frame.set(x, y, REMOVED);
+        });
+        frames.push(frame.clone());
+        round.iter().for_each(|&(x, y)| {
+            frame.set(x, y, EMPTY);
+        });
+    }
+    frames
+}
+
+/// Round (starting at 1) each cell was removed in, if it was removed at all
+pub fn removal_rounds(grid: &Grid<char>, erosion: &Erosion) -> Grid<Option<usize>> {
+    let mut rounds = Grid::new(grid.width(), grid.height(), None);
+    for (round, removed) in erosion.rounds.iter().enumerate() {
+        removed.iter().for_each(|&(x, y)| {
+            rounds.set(x, y, Some(round + 1));
+        });
+    }
+    rounds
+}
+
+// 1-9, then a-z and A-Z; anything later than that is shown as a '+'
+fn round_symbol(round: usize) -> char {
+    const SYMBOLS: &[u8] = b"123456789abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ";
+    SYMBOLS.get(round - 1).map_or('+', |&symbol| symbol as char)
+}
+
+/// Grid where removed rolls show the round they were removed in, and the
+/// remaining core keeps its original symbol.
+pub fn summary(grid: &Grid<char>, erosion: &Erosion) -> String {
+    let rounds = removal_rounds(grid, erosion);
+    render_with(grid, |x, y| match rounds.get(x, y).unwrap() {
+        Some(round) => round_symbol(round),
+        None => grid.get(x, y).unwrap(),
+    })
+}
+
+fn render_with<T, F>(grid: &Grid<T>, symbol: F) -> String
+where
+    F: Fn(usize, usize) -> char,
+{
+    (0..grid.height())
+        .flat_map(|y| (0..grid.width()).map(move |x| (x, y)))
+        .fold(String::new(), |mut out, (x, y)| {
+            out.push(symbol(x, y));
+            if x == grid.width() - 1 {
+                out.push('\n');
+            }
+            out
+        })
+}
+
+pub fn render(frame: &Grid<char>) -> String {
+    render_with(frame, |x, y| frame.get(x, y).unwrap())
+}
+
+/// Plays all frames in the terminal, clearing the screen in between
+pub fn play<W: Write>(frames: &[Grid<char>], delay: Duration, out: &mut W) -> Result<()> {
+    for (round, frame) in frames.iter().enumerate() {
+        // Clear screen and move the cursor to the top left
+        write!(out, "\x1b[2J\x1b[H")?;
+        writeln!(out, "Round {round}")?;
+        for chr in render(frame).chars() {
+            match chr {
+                REMOVED => write!(out, "\x1b[31m{chr}\x1b[0m")?,
+                _ => write!(out, "{chr}")?,
+            }
+        }
+        out.flush()?;
+        std::thread::sleep(delay);
+    }
+    Ok(())
+}
+
+/// Writes a frame as a binary PPM: empty cells are black, rolls are white
+/// and rolls removed in this frame are red.
+pub fn write_ppm<W: Write>(frame: &Grid<char>, out: &mut W) -> Result<()> {
+    write!(out, "P6\n{} {}\n255\n", frame.width(), frame.height())?;
+    for (x, y) in frame.coordinates() {
+        let rgb: [u8; 3] = match frame.get(x, y).unwrap() {
+            EMPTY => [0, 0, 0],
+            REMOVED => [255, 0, 0],
+            _ => [255, 255, 255],
+        };
+        out.write_all(&rgb)?;
+    }
+    Ok(())
+}
+
+/// Writes all frames as frame_0000.ppm, frame_0001.ppm, ... into `dir`
+pub fn write_ppm_frames<P: AsRef<Path>>(frames: &[Grid<char>], dir: P) -> Result<()> {
+    std::fs::create_dir_all(&dir)?;
+    for (round, frame) in frames.iter().enumerate() {
+        let path = dir.as_ref().join(format!("frame_{round:04}.ppm"));
+        let mut file = std::io::BufWriter::new(std::fs::File::create(path)?);
+        write_ppm(frame, &mut file)?;
+    }
+    Ok(())
+}
+
+impl FromStr for Output {
+    type Err = Error;
+
+    /// Parses "stats", "ansi[:DELAY_MS]", "ppm:DIR" or "summary"
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let invalid = || Error::new(ErrorKind::InvalidInput, format!("invalid output '{s}'"));
+        match s.split_once(':') {
+            None if s == "stats" => Ok(Output::Stats),
+            None if s == "summary" => Ok(Output::Summary),
+            None if s == "ansi" => Ok(Output::Ansi(Duration::from_millis(250))),
+            Some(("ansi", delay)) => Ok(Output::Ansi(Duration::from_millis(
+                delay.parse().map_err(|_| invalid())?,
+            ))),
+            Some(("ppm", dir)) if !dir.is_empty() => Ok(Output::Netpbm(dir.into())),
+            _ => Err(invalid()),
+        }
+    }
+}
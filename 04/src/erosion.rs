@@ -0,0 +1,243 @@
+use std::collections::BTreeSet;
+use std::io::{Error, ErrorKind};
+use std::str::FromStr;
+use utils::Grid;
+
+/// Which cells count as neighbors
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum Adjacency {
+    /// Up, down, left and right (von Neumann)
+    Four,
+    /// Including diagonals (Moore)
+    Eight,
+}
+
+/// When reachable rolls are removed
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum Schedule {
+    /// All currently reachable rolls at once, round by round
+    Rounds,
+    /// One at a time in scan order; a removal immediately affects the rolls
+    /// after it. Every pass over the grid counts as a round.
+    Sequential,
+}
+
+/// What makes a roll reachable
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct Rule {
+    pub roll: char,
+    /// A roll is reachable with fewer neighboring rolls than this
+    pub threshold: usize,
+    pub adjacency: Adjacency,
+    pub schedule: Schedule,
+}
+
+impl Default for Rule {
+    fn default() -> Self {
+        Rule {
+            roll: '@',
+            threshold: 4,
+            adjacency: Adjacency::Eight,
+            schedule: Schedule::Rounds,
+        }
+    }
+}
+
+impl Rule {
+    pub fn neighbors<T>(&self, grid: &Grid<T>, x: usize, y: usize) -> Vec<(usize, usize)> {
+        match self.adjacency {
+            Adjacency::Four => grid.orthogonal_neighbors(x, y),
+            Adjacency::Eight => grid.neighbors(x, y),
+        }
+    }
+}
+
+/// Result of removing reachable rolls until none are left
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct Erosion {
+    /// Rolls removed in each round, in order of removal
+    pub rounds: Vec<Vec<(usize, usize)>>,
+    /// Rolls that can never be removed, in scan order
+    pub core: Vec<(usize, usize)>,
+}
+
+impl Erosion {
+    pub fn removed_per_round(&self) -> Vec<usize> {
+        self.rounds.iter().map(|round| round.len()).collect()
+    }
+
+    pub fn total_removed(&self) -> usize {
+        self.rounds.iter().map(|round| round.len()).sum()
+    }
+
+    /// Number of rounds until nothing else can be removed
+    pub fn round_count(&self) -> usize {
+        self.rounds.len()
+    }
+}
+
+// Rolls still in the grid, and how many neighboring rolls each one has
+struct Neighborhood<'a> {
+    grid: &'a Grid<char>,
+    rule: &'a Rule,
+    present: Grid<bool>,
+    counts: Grid<usize>,
+}
+
+impl<'a> Neighborhood<'a> {
+    fn new(grid: &'a Grid<char>, rule: &'a Rule) -> Self {
+        let mut present = Grid::new(grid.width(), grid.height(), false);
+        let mut counts = Grid::new(grid.width(), grid.height(), 0usize);
+        let is_roll = |x: usize, y: usize| grid.get(x, y) == Some(rule.roll);
+
+        for (x, y) in grid.coordinates().filter(|(x, y)| is_roll(*x, *y)) {
+            let count = rule
+                .neighbors(grid, x, y)
+                .into_iter()
+                .filter(|(nx, ny)| is_roll(*nx, *ny))
+                .count();
+            present.set(x, y, true);
+            counts.set(x, y, count);
+        }
+
+        Neighborhood {
+            grid,
+            rule,
+            present,
+            counts,
+        }
+    }
+
+    fn rolls(&self) -> Vec<(usize, usize)> {
+        self.grid
+            .coordinates()
+            .filter(|(x, y)| self.present.get(*x, *y).unwrap())
+            .collect()
+    }
+
+    fn is_reachable(&self, x: usize, y: usize) -> bool {
+        self.present.get(x, y).unwrap() && self.counts.get(x, y).unwrap() < self.rule.threshold
+    }
+
+    // Removes a roll and returns the remaining neighbors that became
+    // reachable because of it. Counts only ever go down, so every roll is
+    // reported at most once.
+    fn remove(&mut self, x: usize, y: usize) -> Vec<(usize, usize)> {
+        self.present.set(x, y, false);
+        let mut reachable = Vec::new();
+        for (nx, ny) in self.rule.neighbors(self.grid, x, y) {
+            if self.present.get(nx, ny).unwrap() {
+                let count = self.counts.get(nx, ny).unwrap() - 1;
+                self.counts.set(nx, ny, count);
+                if count + 1 == self.rule.threshold {
+                    reachable.push((nx, ny));
+                }
+            }
+        }
+        reachable
+    }
+}
+
+/// Removes reachable rolls until none are left. Instead of rescanning the
+/// whole grid, it keeps a count of neighboring rolls for every roll; only the
+/// neighbors of removed rolls are updated and re-checked, which makes the
+/// whole thing O(width * height) (times log n for Schedule::Sequential).
+///
+/// The remaining core is the same for every schedule (it's the k-core of the
+/// grid graph); only the order and number of rounds differ.
+pub fn erode(grid: &Grid<char>, rule: &Rule) -> Erosion {
+    let mut neighborhood = Neighborhood::new(grid, rule);
+    let reachable: Vec<(usize, usize)> = neighborhood
+        .rolls()
+        .into_iter()
+        .filter(|(x, y)| neighborhood.is_reachable(*x, *y))
+        .collect();
+
+    let rounds = match rule.schedule {
+        Schedule::Rounds => erode_rounds(&mut neighborhood, reachable),
+        Schedule::Sequential => erode_sequential(&mut neighborhood, reachable),
+    };
+
+    Erosion {
+        rounds,
+        core: neighborhood.rolls(),
+    }
+}
+
+fn erode_rounds(
+    neighborhood: &mut Neighborhood,
+    mut worklist: Vec<(usize, usize)>,
+) -> Vec<Vec<(usize, usize)>> {
+    let mut rounds = Vec::new();
+    while !worklist.is_empty() {
+        // Newly reachable neighbors have to wait for the next round. Rolls
+        // in this round are reachable already, so they're never reported
+        // again by remove().
+        let next: Vec<(usize, usize)> = worklist
+            .iter()
+            .flat_map(|&(x, y)| neighborhood.remove(x, y))
+            .collect();
+
+        worklist.sort_by_key(|&(x, y)| (y, x));
+        rounds.push(std::mem::replace(&mut worklist, next));
+    }
+    rounds
+}
+
+fn erode_sequential(
+    neighborhood: &mut Neighborhood,
+    reachable: Vec<(usize, usize)>,
+) -> Vec<Vec<(usize, usize)>> {
+    // Ordered by (y, x), so iterating is scan order
+    let mut pass: BTreeSet<(usize, usize)> = reachable.into_iter().map(|(x, y)| (y, x)).collect();
+    let mut rounds = Vec::new();
+
+    while !pass.is_empty() {
+        let mut next_pass = BTreeSet::new();
+        let mut removed = Vec::new();
+
+        while let Some((y, x)) = pass.pop_first() {
+            removed.push((x, y));
+            for (nx, ny) in neighborhood.remove(x, y) {
+                // Still ahead of the scan? Then it's removed in this pass.
+                if (ny, nx) > (y, x) {
+                    pass.insert((ny, nx));
+                } else {
+                    next_pass.insert((ny, nx));
+                }
+            }
+        }
+
+        rounds.push(removed);
+        pass = next_pass;
+    }
+    rounds
+}
+
+impl FromStr for Rule {
+    type Err = Error;
+
+    /// Parses a space separated list of "threshold:N", "adjacency:4|8",
+    /// "roll:C" and "schedule:rounds|sequential"; missing parts are defaults.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid =
+            |part: &str| Error::new(ErrorKind::InvalidInput, format!("invalid rule '{part}'"));
+        let mut rule = Rule::default();
+
+        for part in s.split_whitespace() {
+            match part.split_once(':').ok_or_else(|| invalid(part))? {
+                ("threshold", n) => rule.threshold = n.parse().map_err(|_| invalid(part))?,
+                ("adjacency", "4") => rule.adjacency = Adjacency::Four,
+                ("adjacency", "8") => rule.adjacency = Adjacency::Eight,
+                ("roll", roll) if roll.chars().count() == 1 => {
+                    rule.roll = roll.chars().next().unwrap()
+                }
+                ("schedule", "rounds") => rule.schedule = Schedule::Rounds,
+                ("schedule", "sequential") => rule.schedule = Schedule::Sequential,
+                _ => return Err(invalid(part)),
+            }
+        }
+
+        Ok(rule)
+    }
+}
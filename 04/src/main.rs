@@ -1,5 +1,11 @@
 #![allow(dead_code)]
 
+mod animation;
+mod erosion;
+
+use animation::Output;
+
+use erosion::{Rule, erode};
 use utils::Grid;
 
 fn reachable_from(grid: &Grid<char>, x: usize, y: usize) -> usize {
@@ -21,31 +27,67 @@ fn reachable(grid: &Grid<char>) -> usize {
     reachable_rolls(grid).len()
 }
 
-// PERF(AE): Only the neighbors of any removed roll theoretically need to be
-// re-checked; not the whole grid.
 fn reachable_after_remove(grid: &mut Grid<char>) -> usize {
-    let mut total: usize = 0;
-    loop {
-        let removable = reachable_rolls(grid);
-        if removable.is_empty() {
-            break;
-        }
-        total += removable.len();
-        for (x, y) in removable {
-            grid.set(x, y, '.');
-        }
+    let erosion = erode(grid, &Rule::default());
+    for (x, y) in erosion.rounds.iter().flatten() {
+        grid.set(*x, *y, '.');
     }
-    total
+    erosion.total_removed()
 }
 
-fn main() {
-    println!("Run 'cargo test' instead ;)");
+fn main() -> std::io::Result<()> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.is_empty() {
+        println!("Run 'cargo test' instead ;)");
+        println!(
+            "Or: day_04 <file> [threshold:N] [adjacency:4|8] [roll:C] [schedule:rounds|sequential] \
+             [output:stats|output:summary|output:ansi[:DELAY_MS]|output:ppm:DIR]"
+        );
+        return Ok(());
+    }
+
+    let (outputs, rules): (Vec<&String>, Vec<&String>) =
+        args[1..].iter().partition(|arg| arg.starts_with("output:"));
+    let output: Output = match outputs.last() {
+        Some(output) => output["output:".len()..].parse()?,
+        None => Output::Stats,
+    };
+    let rule: Rule = rules
+        .iter()
+        .map(|rule| rule.as_str())
+        .collect::<Vec<_>>()
+        .join(" ")
+        .parse()?;
+
+    let grid = Grid::<char>::from_file(&args[0])?;
+    let erosion = erode(&grid, &rule);
+
+    match output {
+        Output::Stats => {}
+        Output::Summary => print!("{}", animation::summary(&grid, &erosion)),
+        Output::Ansi(delay) => animation::play(
+            &animation::frames(&grid, &erosion),
+            delay,
+            &mut std::io::stdout().lock(),
+        )?,
+        Output::Netpbm(dir) => {
+            animation::write_ppm_frames(&animation::frames(&grid, &erosion), dir)?
+        }
+    }
+
+    println!("Removed per round: {:?}", erosion.removed_per_round());
+    println!("Removed: {}", erosion.total_removed());
+    println!("Rounds: {}", erosion.round_count());
+    println!("Core: {}", erosion.core.len());
+    Ok(())
 }
 
 #[cfg(test)]
 mod tests {
     mod day04_printing_department {
+        use super::super::erosion::{Adjacency, Schedule};
         use super::super::*;
+        use utils::Rng;
 
         #[test]
         fn sample() {
@@ -54,6 +96,172 @@ mod tests {
             assert_eq!(reachable_after_remove(&mut grid), 43);
         }
 
+        // The original implementation, rescanning the whole grid every round
+        fn remove_by_rescan(grid: &mut Grid<char>) -> Vec<usize> {
+            let mut rounds = Vec::new();
+            loop {
+                let removable = reachable_rolls(grid);
+                if removable.is_empty() {
+                    break rounds;
+                }
+                rounds.push(removable.len());
+                for (x, y) in removable {
+                    grid.set(x, y, '.');
+                }
+            }
+        }
+
+        #[test]
+        fn sample_rounds() {
+            let grid = Grid::<char>::from_file("data/sample.txt").unwrap();
+            let erosion = erode(&grid, &Rule::default());
+            assert_eq!(
+                erosion.removed_per_round(),
+                vec![13, 12, 7, 5, 2, 1, 1, 1, 1]
+            );
+            assert_eq!(erosion.round_count(), 9);
+            assert_eq!(erosion.rounds[0], reachable_rolls(&grid));
+            assert_eq!(erosion.core.len(), 71 - 43);
+        }
+
+        #[test]
+        fn generated_warehouses() {
+            let mut rng = Rng::new(4);
+            for _ in 0..20 {
+                let (width, height) = (rng.range(1..60) as usize, rng.range(1..60) as usize);
+                let density = rng.range(50..95);
+                let mut grid = Grid::new(width, height, '.');
+                for (x, y) in grid.coordinates().collect::<Vec<_>>() {
+                    if rng.range(0..100) < density {
+                        grid.set(x, y, '@');
+                    }
+                }
+
+                let erosion = erode(&grid, &Rule::default());
+                let rounds = remove_by_rescan(&mut grid);
+                assert_eq!(erosion.removed_per_round(), rounds);
+                assert_eq!(
+                    erosion.core,
+                    grid.coordinates()
+                        .filter(|(x, y)| grid.get(*x, *y) == Some('@'))
+                        .collect::<Vec<_>>()
+                );
+            }
+        }
+
+        #[test]
+        fn rules() {
+            let grid = Grid::<char>::from_file("data/sample.txt").unwrap();
+            let sequential = Rule {
+                schedule: Schedule::Sequential,
+                ..Rule::default()
+            };
+            let erosion = erode(&grid, &sequential);
+            assert_eq!(erosion.total_removed(), 43);
+            assert!(erosion.round_count() < 9);
+            assert_eq!(erosion.core, erode(&grid, &Rule::default()).core);
+
+            // With a threshold of 9, all 8 neighbors can be rolls
+            let rule: Rule = "threshold:9".parse().unwrap();
+            assert_eq!(erode(&grid, &rule).core.len(), 0);
+            assert_eq!(erode(&grid, &rule).round_count(), 1);
+
+            // Nothing is reachable with a threshold of 0; a different symbol
+            // means there are no rolls at all.
+            assert_eq!(
+                erode(&grid, &"threshold:0".parse().unwrap()).round_count(),
+                0
+            );
+            assert_eq!(erode(&grid, &"roll:#".parse().unwrap()).core.len(), 0);
+
+            let rule: Rule = "adjacency:4 threshold:2 schedule:sequential roll:."
+                .parse()
+                .unwrap();
+            assert_eq!(rule.adjacency, Adjacency::Four);
+            assert_eq!(rule.roll, '.');
+            assert!("adjacency:6".parse::<Rule>().is_err());
+            assert!("roll:ab".parse::<Rule>().is_err());
+        }
+
+        #[test]
+        fn generated_rules() {
+            let mut rng = Rng::new(33);
+            for _ in 0..50 {
+                let (width, height) = (rng.range(1..40) as usize, rng.range(1..40) as usize);
+                let mut grid = Grid::new(width, height, '.');
+                for (x, y) in grid.coordinates().collect::<Vec<_>>() {
+                    if rng.range(0..100) < 80 {
+                        grid.set(x, y, '@');
+                    }
+                }
+
+                let rule = Rule {
+                    threshold: rng.range(0..9) as usize,
+                    adjacency: if rng.bool() {
+                        Adjacency::Four
+                    } else {
+                        Adjacency::Eight
+                    },
+                    ..Rule::default()
+                };
+                let sequential = Rule {
+                    schedule: Schedule::Sequential,
+                    ..rule
+                };
+
+                let rounds = erode(&grid, &rule);
+                let scanned = erode(&grid, &sequential);
+                assert_eq!(rounds.core, scanned.core);
+                assert_eq!(rounds.total_removed(), scanned.total_removed());
+                assert!(scanned.round_count() <= rounds.round_count());
+
+                // Every core roll has enough neighbors in the core to stay
+                for (x, y) in &rounds.core {
+                    let count = rule
+                        .neighbors(&grid, *x, *y)
+                        .into_iter()
+                        .filter(|neighbor| rounds.core.contains(neighbor))
+                        .count();
+                    assert!(count >= rule.threshold);
+                }
+            }
+        }
+
+        #[test]
+        fn animation() {
+            let grid = Grid::<char>::from_file("data/sample.txt").unwrap();
+            let erosion = erode(&grid, &Rule::default());
+
+            let frames = animation::frames(&grid, &erosion);
+            assert_eq!(frames.len(), 10);
+            assert_eq!(
+                animation::render(&frames[0]),
+                std::fs::read_to_string("data/sample.txt").unwrap()
+            );
+            assert!(animation::render(&frames[1]).starts_with("..xx.xx@x."));
+            assert!(animation::render(&frames[2]).starts_with(".......x.."));
+
+            let summary = animation::summary(&grid, &erosion);
+            assert_eq!(summary.lines().next(), Some("..11.1121."));
+            assert_eq!(summary.matches('@').count(), erosion.core.len());
+            assert_eq!(summary.matches('9').count(), 1);
+
+            let mut ppm = Vec::new();
+            animation::write_ppm(&frames[1], &mut ppm).unwrap();
+            assert!(ppm.starts_with(b"P6\n10 10\n255\n"));
+            assert_eq!(ppm.len(), b"P6\n10 10\n255\n".len() + 10 * 10 * 3);
+            assert_eq!(&ppm[ppm.len() - 30 + 6..][..3], &[255, 0, 0]);
+
+            let mut ansi = Vec::new();
+            animation::play(&frames[..2], std::time::Duration::ZERO, &mut ansi).unwrap();
+            let ansi = String::from_utf8(ansi).unwrap();
+            assert_eq!(ansi.matches("Round ").count(), 2);
+            assert_eq!(ansi.matches("\x1b[31mx").count(), 13);
+
+            assert_eq!("summary".parse::<Output>().unwrap(), Output::Summary);
+            assert!("ppm:".parse::<Output>().is_err());
+        }
+
         #[test]
         #[cfg(feature = "private")]
         fn input() {
@@ -1,9 +1,12 @@
 #![allow(dead_code)]
 
+mod worksheet;
+
 use utils::Grid;
 use utils::math::transpose;
+use worksheet::Direction;
 
-use std::io::Result;
+use std::io::{Error, ErrorKind, Result};
 use std::path::Path;
 
 fn human_math<P: AsRef<Path>>(path: P) -> Result<u64> {
@@ -32,39 +35,60 @@ fn human_math<P: AsRef<Path>>(path: P) -> Result<u64> {
     )
 }
 
-// Takes a vertical slice of characters and parses it into a number
-fn ceph_operand(grid: &Grid<char>, x: usize) -> u64 {
-    (0..grid.height() - 1)
+// Takes a vertical slice of characters above the operator row and parses it
+// into a number. Columns without any digits (below a wider number, or above
+// a wider operator) are not operands at all.
+fn ceph_operand(grid: &Grid<char>, x: usize, operator_row: usize) -> Option<u64> {
+    (0..operator_row)
         .map(|y| grid.get(x, y).unwrap())
         .filter(|chr| chr.is_ascii_digit())
         .map(|chr| (chr as u8 - b'0') as u64)
-        .fold(0u64, |acc, digit| acc * 10 + digit)
+        .fold(None, |acc, digit| Some(acc.unwrap_or(0) * 10 + digit))
+}
+
+// Problems are separated by columns made entirely of spaces
+fn is_separator(grid: &Grid<char>, x: usize) -> bool {
+    (0..grid.height()).all(|y| grid.get(x, y) == Some(' '))
 }
 
 fn ceph_math<P: AsRef<Path>>(path: P) -> Result<u64> {
     let grid = Grid::<char>::from_file(path)?;
 
-    Ok((0..grid.width())
-        .rev()
-        .fold((0u64, Vec::<u64>::new()), |(total, mut ops), x| {
-            let op = ceph_operand(&grid, x);
-            ops.push(op);
-
-            (
-                match grid.get(x, grid.height() - 1).unwrap() {
-                    '+' => ops.iter().sum(),
-                    '*' => ops.iter().product(),
-                    _ => {
-                        if op == 0 {
-                            ops.clear();
-                        }
-                        0
-                    }
-                } + total,
-                ops,
-            )
+    // The operators are in the last row, below all the operands
+    let operator_row = grid.height() - 1;
+    let columns: Vec<usize> = (0..grid.width()).rev().collect();
+
+    columns
+        .split(|&x| is_separator(&grid, x))
+        .filter(|problem| !problem.is_empty())
+        .map(|problem| {
+            let operands = problem
+                .iter()
+                .filter_map(|&x| ceph_operand(&grid, x, operator_row));
+
+            // The operator can be below any of the problem's columns
+            match problem
+                .iter()
+                .map(|&x| grid.get(x, operator_row).unwrap())
+                .find(|chr| *chr != ' ')
+            {
+                Some('+') => Ok(operands.sum::<u64>()),
+                Some('*') => Ok(operands.product::<u64>()),
+                other => Err(Error::new(
+                    ErrorKind::InvalidData,
+                    format!("unknown operator {other:?}"),
+                )),
+            }
         })
-        .0)
+        .sum()
+}
+
+// Same as human_math() and ceph_math(), parsing into problems first
+fn solve<P: AsRef<Path>>(path: P, direction: Direction) -> Result<u64> {
+    worksheet::total(&worksheet::parse(
+        &std::fs::read_to_string(path)?,
+        direction,
+    )?)
 }
 
 fn main() {
@@ -74,6 +98,7 @@ fn main() {
 #[cfg(test)]
 mod tests {
     mod day06_trash_compactor {
+        use super::super::worksheet::{Operator, Problem};
         use super::super::*;
 
         #[test]
@@ -82,11 +107,75 @@ mod tests {
             assert_eq!(ceph_math("data/sample.txt").unwrap(), 3263827);
         }
 
+        #[test]
+        fn zeros() {
+            // Operands of 0 and operators that aren't in the leftmost column
+            assert_eq!(ceph_math("data/zeros.txt").unwrap(), 76);
+            assert_eq!(solve("data/zeros.txt", Direction::Columns).unwrap(), 76);
+            assert_eq!(human_math("data/zeros.txt").unwrap(), 157);
+            assert_eq!(solve("data/zeros.txt", Direction::Rows).unwrap(), 157);
+        }
+
+        #[test]
+        fn sample_parsed() {
+            assert_eq!(solve("data/sample.txt", Direction::Rows).unwrap(), 4277556);
+            assert_eq!(
+                solve("data/sample.txt", Direction::Columns).unwrap(),
+                3263827
+            );
+
+            let text = std::fs::read_to_string("data/sample.txt").unwrap();
+            let problems = worksheet::parse(&text, Direction::Columns).unwrap();
+            assert_eq!(
+                problems[3],
+                Problem {
+                    operands: vec![4, 431, 623],
+                    op: Operator::Add
+                }
+            );
+        }
+
+        #[test]
+        fn operators() {
+            let evaluate = |text: &str, direction| {
+                let problems = worksheet::parse(text, direction)?;
+                worksheet::total(&problems)
+            };
+
+            let text = "20 7   3  12\n 5 2  10   9\n-  / min max";
+            assert_eq!(evaluate(text, Direction::Rows).unwrap(), 15 + 3 + 3 + 12);
+            assert_eq!(
+                evaluate("9\n0\n/", Direction::Rows)
+                    .unwrap_err()
+                    .to_string(),
+                "problem 1: can't be evaluated"
+            );
+            assert_eq!(
+                evaluate("1 2\n+ %", Direction::Rows)
+                    .unwrap_err()
+                    .to_string(),
+                "problem 2: unknown operator '%'"
+            );
+
+            // Overflows are errors, not wrapped (or 0)
+            let text = "18446744073709551615\n                   1\n+";
+            assert!(evaluate(text, Direction::Rows).is_err());
+            assert!(evaluate(text, Direction::Columns).is_ok());
+        }
+
         #[test]
         #[cfg(feature = "private")]
         fn input() {
             assert_eq!(human_math("data/input.txt").unwrap(), 6169101504608);
             assert_eq!(ceph_math("data/input.txt").unwrap(), 10442199710797);
+            assert_eq!(
+                solve("data/input.txt", Direction::Rows).unwrap(),
+                6169101504608
+            );
+            assert_eq!(
+                solve("data/input.txt", Direction::Columns).unwrap(),
+                10442199710797
+            );
         }
     }
 }
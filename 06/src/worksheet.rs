@@ -0,0 +1,153 @@
+use std::io::{Error, ErrorKind, Result};
+use std::str::FromStr;
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum Operator {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Min,
+    Max,
+}
+
+/// How the numbers of a problem are written down
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum Direction {
+    /// One number per row, read left to right (human math)
+    Rows,
+    /// One number per column, read top to bottom; the columns are read
+    /// right to left (cephalopod math)
+    Columns,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Problem {
+    /// In reading order, which matters for subtraction and division
+    pub operands: Vec<u64>,
+    pub op: Operator,
+}
+
+impl FromStr for Operator {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "+" => Ok(Operator::Add),
+            "-" => Ok(Operator::Subtract),
+            "*" => Ok(Operator::Multiply),
+            "/" => Ok(Operator::Divide),
+            "min" => Ok(Operator::Min),
+            "max" => Ok(Operator::Max),
+            _ => Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("unknown operator '{s}'"),
+            )),
+        }
+    }
+}
+
+impl Problem {
+    /// Result of the problem, or None on overflow, underflow, division by zero
+    /// or a problem without operands.
+    pub fn evaluate(&self) -> Option<u64> {
+        let (first, rest) = self.operands.split_first()?;
+        rest.iter().try_fold(*first, |acc, &n| match self.op {
+            Operator::Add => acc.checked_add(n),
+            Operator::Subtract => acc.checked_sub(n),
+            Operator::Multiply => acc.checked_mul(n),
+            Operator::Divide => acc.checked_div(n),
+            Operator::Min => Some(acc.min(n)),
+            Operator::Max => Some(acc.max(n)),
+        })
+    }
+}
+
+fn invalid(what: String) -> Error {
+    Error::new(ErrorKind::InvalidData, what)
+}
+
+// Number from a row or column of a problem, or None if it's blank
+fn to_number(chars: impl Iterator<Item = char>) -> Result<Option<u64>> {
+    let mut number: Option<u64> = None;
+    for chr in chars.filter(|chr| *chr != ' ') {
+        let digit = chr
+            .to_digit(10)
+            .ok_or_else(|| invalid(format!("invalid digit '{chr}'")))?;
+        number = Some(
+            number
+                .unwrap_or(0)
+                .checked_mul(10)
+                .and_then(|n| n.checked_add(digit.into()))
+                .ok_or_else(|| invalid("operand too large".into()))?,
+        );
+    }
+    Ok(number)
+}
+
+/// Splits the worksheet into problems. Problems are separated by columns
+/// made entirely of spaces and the last line holds the operators.
+pub fn parse(worksheet: &str, direction: Direction) -> Result<Vec<Problem>> {
+    let lines: Vec<Vec<char>> = worksheet
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| line.chars().collect())
+        .collect();
+    let (operators, numbers) = lines
+        .split_last()
+        .ok_or_else(|| invalid("empty worksheet".into()))?;
+
+    let width = lines.iter().map(|line| line.len()).max().unwrap();
+    let cell = |line: &[char], x: usize| line.get(x).copied().unwrap_or(' ');
+    let is_separator = |x: usize| lines.iter().all(|line| cell(line, x) == ' ');
+
+    // Start and end (exclusive) column of every problem
+    let mut spans: Vec<(usize, usize)> = Vec::new();
+    for x in (0..width).filter(|x| !is_separator(*x)) {
+        match spans.last_mut() {
+            Some((_, end)) if *end == x => *end = x + 1,
+            _ => spans.push((x, x + 1)),
+        }
+    }
+
+    spans
+        .into_iter()
+        .enumerate()
+        .map(|(idx, (start, end))| {
+            let context = |e: Error| invalid(format!("problem {}: {e}", idx + 1));
+            let op_text: String = (start..end).map(|x| cell(operators, x)).collect();
+            let op: Operator = op_text.trim().parse().map_err(context)?;
+
+            let operands: Vec<Option<u64>> = match direction {
+                Direction::Rows => numbers
+                    .iter()
+                    .map(|line| to_number((start..end).map(|x| cell(line, x))))
+                    .collect::<Result<_>>(),
+                Direction::Columns => (start..end)
+                    .rev()
+                    .map(|x| to_number(numbers.iter().map(|line| cell(line, x))))
+                    .collect::<Result<_>>(),
+            }
+            .map_err(context)?;
+
+            Ok(Problem {
+                operands: operands.into_iter().flatten().collect(),
+                op,
+            })
+        })
+        .collect()
+}
+
+/// Sum of all problem results, or an error on overflow (or any other
+/// problem that can't be evaluated).
+pub fn total(problems: &[Problem]) -> Result<u64> {
+    problems
+        .iter()
+        .enumerate()
+        .try_fold(0u64, |sum, (idx, problem)| {
+            problem
+                .evaluate()
+                .and_then(|result| sum.checked_add(result))
+                .ok_or_else(|| invalid(format!("problem {}: can't be evaluated", idx + 1)))
+        })
+}
@@ -0,0 +1,146 @@
+use std::io::{Error, ErrorKind};
+use std::str::FromStr;
+
+/// Number type to count timelines with
+pub trait Count: Clone {
+    fn zero() -> Self;
+    fn one() -> Self;
+    /// None if the result doesn't fit
+    fn checked_add(&self, other: &Self) -> Option<Self>;
+}
+
+impl Count for u64 {
+    fn zero() -> Self {
+        0
+    }
+
+    fn one() -> Self {
+        1
+    }
+
+    fn checked_add(&self, other: &Self) -> Option<Self> {
+        u64::checked_add(*self, *other)
+    }
+}
+
+impl Count for u128 {
+    fn zero() -> Self {
+        0
+    }
+
+    fn one() -> Self {
+        1
+    }
+
+    fn checked_add(&self, other: &Self) -> Option<Self> {
+        u128::checked_add(*self, *other)
+    }
+}
+
+/// Unsigned integer that never overflows. Only supports what's needed to
+/// count (addition) and show the result.
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+pub struct BigUint {
+    // Least significant first, without trailing (most significant) zeros
+    limbs: Vec<u32>,
+}
+
+impl From<u128> for BigUint {
+    fn from(mut n: u128) -> Self {
+        let mut limbs = Vec::new();
+        while n > 0 {
+            limbs.push(n as u32);
+            n >>= 32;
+        }
+        BigUint { limbs }
+    }
+}
+
+impl From<u64> for BigUint {
+    fn from(n: u64) -> Self {
+        BigUint::from(n as u128)
+    }
+}
+
+impl Count for BigUint {
+    fn zero() -> Self {
+        BigUint::default()
+    }
+
+    fn one() -> Self {
+        BigUint { limbs: vec![1] }
+    }
+
+    fn checked_add(&self, other: &Self) -> Option<Self> {
+        let len = self.limbs.len().max(other.limbs.len());
+        let mut limbs = Vec::with_capacity(len + 1);
+        let mut carry = 0u64;
+        for idx in 0..len {
+            let sum = carry
+                + *self.limbs.get(idx).unwrap_or(&0) as u64
+                + *other.limbs.get(idx).unwrap_or(&0) as u64;
+            limbs.push(sum as u32);
+            carry = sum >> 32;
+        }
+        if carry > 0 {
+            limbs.push(carry as u32);
+        }
+        Some(BigUint { limbs })
+    }
+}
+
+impl std::fmt::Display for BigUint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // Repeatedly divide by 10^9 and collect the remainders
+        const CHUNK: u64 = 1_000_000_000;
+        let mut limbs = self.limbs.clone();
+        let mut chunks: Vec<u64> = Vec::new();
+
+        while !limbs.is_empty() {
+            let mut remainder = 0u64;
+            for limb in limbs.iter_mut().rev() {
+                let value = (remainder << 32) | *limb as u64;
+                *limb = (value / CHUNK) as u32;
+                remainder = value % CHUNK;
+            }
+            chunks.push(remainder);
+            while limbs.last() == Some(&0) {
+                limbs.pop();
+            }
+        }
+
+        match chunks.split_last() {
+            None => write!(f, "0"),
+            Some((first, rest)) => {
+                write!(f, "{first}")?;
+                rest.iter()
+                    .rev()
+                    .try_for_each(|chunk| write!(f, "{chunk:09}"))
+            }
+        }
+    }
+}
+
+/// Count type, selected at runtime
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum Precision {
+    U64,
+    U128,
+    Big,
+}
+
+impl FromStr for Precision {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "u64" => Ok(Precision::U64),
+            "u128" => Ok(Precision::U128),
+            "big" => Ok(Precision::Big),
+            _ => Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("invalid precision '{s}'"),
+            )),
+        }
+    }
+}
@@ -1,11 +1,25 @@
 #![allow(dead_code)]
 
-use std::io::Result;
+mod count;
+mod manifold;
+mod timeline;
+
+use count::{BigUint, Count, Precision};
+use manifold::{Config, Manifold};
+use std::io::{Error, ErrorKind, Result};
 use std::path::Path;
 use utils::Grid;
 
 fn tachy_splits<P: AsRef<Path>>(path: P) -> Result<u64> {
-    let mut grid = Grid::<char>::from_file(path)?;
+    let grid = Grid::<char>::from_file(path)?;
+    Ok(splits(grid, Config::default()))
+}
+
+fn splits(mut grid: Grid<char>, config: Config) -> u64 {
+    if !manifold::is_simple(&grid, &config) {
+        return Manifold::new(&grid, config).splits() as u64;
+    }
+
     let start = grid.find('S').unwrap();
     let mut rays = vec![start];
     let mut splits = 0u64;
@@ -40,10 +54,16 @@ fn tachy_splits<P: AsRef<Path>>(path: P) -> Result<u64> {
         }
     }
 
-    Ok(splits)
+    splits
 }
 
-fn count_leaves(grid: &Grid<char>, leaf_map: &Grid<usize>, x: usize, y: usize) -> usize {
+// None if the count doesn't fit into C
+fn count_leaves<C: Count>(
+    grid: &Grid<char>,
+    leaf_map: &Grid<Option<C>>,
+    x: usize,
+    y: usize,
+) -> Option<C> {
     (y + 1..grid.height())
         .find_map(|scan_y| {
             // The .then() here converta a bool to an Option<T>
@@ -52,40 +72,383 @@ fn count_leaves(grid: &Grid<char>, leaf_map: &Grid<usize>, x: usize, y: usize) -
                 [x.wrapping_sub(1), x + 1]
                     .iter()
                     .filter(|&&nx| nx < grid.width())
-                    .map(|&nx| leaf_map.get(nx, scan_y).unwrap())
-                    .sum()
+                    .try_fold(C::zero(), |sum, &nx| {
+                        sum.checked_add(&leaf_map.get(nx, scan_y).unwrap()?)
+                    })
             })
         })
         // If we reached the bottom of the grid, we're a leaf...
-        .unwrap_or(1)
+        .unwrap_or(Some(C::one()))
 }
 
-fn tachy_leaves<P: AsRef<Path>>(path: P) -> Result<usize> {
-    let grid = Grid::<char>::from_file(path)?;
+fn leaves<C: Count>(grid: &Grid<char>, config: Config) -> Result<C> {
+    // Beams going anywhere but down need the more general (and slower) graph
+    if !manifold::is_simple(grid, &config) {
+        return Manifold::new(grid, config).leaves();
+    }
+
     let start = grid.find('S').unwrap();
-    let mut leaf_map = Grid::new(grid.width(), grid.height(), 0usize);
+    let mut leaf_map: Grid<Option<C>> = Grid::new(grid.width(), grid.height(), None);
 
-    // Iterate the grid bottom up, to fill the map without recursion
+    // Iterate the grid bottom up, to fill the map without recursion. Counts that
+    // overflow are kept as None; that's only an error if the start overflows.
     grid.coordinates_rev().for_each(|(x, y)| {
-        leaf_map.set(x, y, count_leaves(&grid, &leaf_map, x, y));
+        leaf_map.set(x, y, count_leaves(grid, &leaf_map, x, y));
     });
 
-    Ok(leaf_map.get(start.0, start.1).unwrap())
+    leaf_map
+        .get(start.0, start.1)
+        .unwrap()
+        .ok_or_else(|| Error::new(ErrorKind::InvalidData, "timeline count overflow"))
+}
+
+/// Both answers in a single top down sweep: the number of splitters hit and
+/// the number of timelines. Only keeps the number of timelines for every
+/// column of the current row (None if there's no beam), so this is O(h·w).
+fn sweep<C: Count>(grid: &Grid<char>) -> Result<(u64, C)> {
+    let overflow = || Error::new(ErrorKind::InvalidData, "timeline count overflow");
+    let start = grid.find('S').unwrap();
+    let mut beams: Vec<Option<C>> = vec![None; grid.width()];
+    beams[start.0] = Some(C::one());
+    let mut splits = 0u64;
+
+    for y in start.1 + 1..grid.height() {
+        let hit: Vec<usize> = (0..grid.width())
+            .filter(|&x| grid.get(x, y) == Some('^') && beams[x].is_some())
+            .collect();
+        // Beams stop at their splitter before any of them branch out, as a
+        // splitter right next to it may send beams into its column
+        let mut next = beams.clone();
+        hit.iter().for_each(|&x| next[x] = None);
+        splits += hit.len() as u64;
+
+        for &x in &hit {
+            let count = beams[x].as_ref().unwrap();
+            for nx in [x.wrapping_sub(1), x + 1]
+                .into_iter()
+                .filter(|&nx| nx < grid.width())
+            {
+                next[nx] = Some(match &next[nx] {
+                    Some(other) => other.checked_add(count).ok_or_else(overflow)?,
+                    None => count.clone(),
+                });
+            }
+        }
+        beams = next;
+    }
+
+    let timelines = beams
+        .iter()
+        .flatten()
+        .try_fold(C::zero(), |sum, count| sum.checked_add(count))
+        .ok_or_else(overflow)?;
+    Ok((splits, timelines))
+}
+
+fn tachy_sweep<P: AsRef<Path>>(path: P) -> Result<(u64, u64)> {
+    sweep(&Grid::<char>::from_file(path)?)
 }
 
-fn main() {
-    println!("Run 'cargo test' instead ;)");
+// Both answers, with the fastest algorithm the manifold allows
+fn solve<C: Count>(grid: &Grid<char>, config: Config) -> Result<(u64, C)> {
+    if manifold::is_simple(grid, &config) {
+        return sweep(grid);
+    }
+    let manifold = Manifold::new(grid, config);
+    Ok((manifold.splits() as u64, manifold.leaves()?))
+}
+
+fn solve_as(grid: &Grid<char>, precision: Precision, config: Config) -> Result<(u64, BigUint)> {
+    match precision {
+        Precision::U64 => solve::<u64>(grid, config).map(|(s, t)| (s, t.into())),
+        Precision::U128 => solve::<u128>(grid, config).map(|(s, t)| (s, t.into())),
+        Precision::Big => solve::<BigUint>(grid, config),
+    }
+}
+
+fn tachy_leaves<P: AsRef<Path>>(path: P) -> Result<u64> {
+    leaves(&Grid::<char>::from_file(path)?, Config::default())
+}
+
+fn tachy_leaves_as<P: AsRef<Path>>(
+    path: P,
+    precision: Precision,
+    config: Config,
+) -> Result<BigUint> {
+    let grid = Grid::<char>::from_file(path)?;
+    match precision {
+        Precision::U64 => leaves::<u64>(&grid, config).map(BigUint::from),
+        Precision::U128 => leaves::<u128>(&grid, config).map(BigUint::from),
+        Precision::Big => leaves::<BigUint>(&grid, config),
+    }
+}
+
+fn main() -> Result<()> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.is_empty() {
+        println!("Run 'cargo test' instead ;)");
+        println!("Or: day_07 <file> [u64|u128|big] [fan-out:N]");
+        return Ok(());
+    }
+
+    let mut precision = Precision::U64;
+    let mut config = Config::default();
+    for arg in &args[1..] {
+        match arg.strip_prefix("fan-out:") {
+            Some(fan_out) => {
+                config.fan_out = fan_out
+                    .parse()
+                    .map_err(|_| Error::new(ErrorKind::InvalidInput, "invalid fan-out"))?
+            }
+            None => precision = arg.parse()?,
+        }
+    }
+
+    let grid = Grid::<char>::from_file(&args[0])?;
+    let (splits, timelines) = solve_as(&grid, precision, config)?;
+    println!("Splits: {splits}");
+    println!("Timelines: {timelines}");
+    Ok(())
 }
 
 #[cfg(test)]
 mod tests {
     mod day07_laboratories {
+        use super::super::timeline::{Choice, Timeline, Timelines};
         use super::super::*;
+        use utils::Rng;
 
         #[test]
         fn sample() {
             assert_eq!(tachy_splits("data/sample.txt").unwrap(), 21);
             assert_eq!(tachy_leaves("data/sample.txt").unwrap(), 40);
+            assert_eq!(tachy_sweep("data/sample.txt").unwrap(), (21, 40));
+        }
+
+        // Random manifold in the style of the puzzle input: splitters on every
+        // other row, never next to each other or at the edge.
+        fn random_manifold(rng: &mut Rng, width: usize, height: usize) -> Grid<char> {
+            let mut grid = Grid::new(width, height, '.');
+            grid.set(1 + rng.index(width - 2), 0, 'S');
+            for y in (2..height).step_by(2) {
+                for x in (1..width - 1).filter(|x| x % 2 == y / 2 % 2) {
+                    if rng.range(0..3) == 0 {
+                        grid.set(x, y, '^');
+                    }
+                }
+            }
+            grid
+        }
+
+        // Random manifold with splitters anywhere below the start, including
+        // right next to each other and at the edge
+        fn random_crowded_manifold(rng: &mut Rng, width: usize, height: usize) -> Grid<char> {
+            let mut grid = Grid::new(width, height, '.');
+            grid.set(rng.index(width), 0, 'S');
+            for y in 1..height {
+                for x in 0..width {
+                    if rng.range(0..3) == 0 {
+                        grid.set(x, y, '^');
+                    }
+                }
+            }
+            grid
+        }
+
+        #[test]
+        fn sweep_matches_reference() {
+            let mut rng = Rng::new(40);
+            for _ in 0..500 {
+                let width = 3 + rng.index(20);
+                let height = 1 + rng.index(30);
+                let grid = random_manifold(&mut rng, width, height);
+                let expected = (
+                    splits(grid.clone(), Config::default()),
+                    leaves::<u64>(&grid, Config::default()).unwrap(),
+                );
+                assert_eq!(sweep(&grid).unwrap(), expected);
+                assert_eq!(Timelines::new(&grid).count().unwrap(), expected.1);
+            }
+
+            assert!(sweep::<u64>(&pyramid(64)).is_err());
+            assert_eq!(
+                sweep::<BigUint>(&pyramid(64)).unwrap(),
+                (64 * 65 / 2, BigUint::from(1u128 << 64))
+            );
+        }
+
+        #[test]
+        fn sweep_adjacent_splitters() {
+            // The splitter on the right gets a beam from the one on its left,
+            // which has to go on past it
+            let grid = manifold(&[
+                "..S...", "......", "..^...", "......", "...^..", "......", ".^^...", "......",
+            ]);
+            assert_eq!(leaves::<u64>(&grid, Config::default()).unwrap(), 5);
+            assert_eq!(Timelines::new(&grid).count().unwrap(), 5);
+            assert_eq!(sweep::<u64>(&grid).unwrap(), (4, 5));
+
+            // The ray tracing in splits() doesn't send beams into a
+            // neighboring splitter, so count splitters on the graph instead
+            let mut rng = Rng::new(41);
+            for _ in 0..500 {
+                let width = 1 + rng.index(20);
+                let height = 1 + rng.index(30);
+                let grid = random_crowded_manifold(&mut rng, width, height);
+                let expected = (
+                    Manifold::new(&grid, Config::default()).splits() as u64,
+                    leaves::<u64>(&grid, Config::default()).unwrap(),
+                );
+                assert_eq!(sweep(&grid).unwrap(), expected);
+                assert_eq!(Timelines::new(&grid).count().unwrap(), expected.1);
+            }
+        }
+
+        #[test]
+        fn sample_timelines() {
+            let grid = Grid::<char>::from_file("data/sample.txt").unwrap();
+            let timelines = Timelines::new(&grid);
+            assert_eq!(timelines.count().unwrap(), 40);
+
+            let all: Vec<Timeline> = timelines.iter().unwrap().collect();
+            assert_eq!(all.len(), 40);
+            assert!(all.windows(2).all(|pair| pair[0].choices < pair[1].choices));
+            assert_eq!(all[0].choices, vec![Choice::Left; 7]);
+            assert_eq!(all[0].exit, 0);
+            assert_eq!(all[39].choices, vec![Choice::Right; 7]);
+            assert_eq!(
+                all[39].splitters,
+                vec![
+                    (7, 2),
+                    (8, 4),
+                    (9, 6),
+                    (10, 8),
+                    (11, 10),
+                    (12, 12),
+                    (13, 14)
+                ]
+            );
+            assert_eq!(all[39].exit, 14);
+            assert_eq!(timelines.nth(40).unwrap(), None);
+
+            let mut histogram = vec![0; grid.width()];
+            all.iter()
+                .for_each(|timeline| histogram[timeline.exit] += 1);
+            assert_eq!(timelines.exit_histogram().unwrap(), histogram);
+
+            let mut rng = Rng::new(7);
+            let sample = timelines.sample(&mut rng).unwrap().unwrap();
+            assert!(all.contains(&sample));
+        }
+
+        // Every splitter row doubles the number of timelines
+        fn pyramid(levels: usize) -> Grid<char> {
+            let width = 2 * levels + 3;
+            let center = levels + 1;
+            let mut grid = Grid::new(width, 2 * levels + 2, '.');
+            grid.set(center, 0, 'S');
+            for level in 0..levels {
+                for splitter in 0..=level {
+                    grid.set(center - level + 2 * splitter, 2 * level + 2, '^');
+                }
+            }
+            grid
+        }
+
+        #[test]
+        fn precision() {
+            assert_eq!(
+                tachy_leaves_as("data/sample.txt", Precision::Big, Config::default()).unwrap(),
+                BigUint::from(40u64)
+            );
+            assert_eq!(
+                leaves::<u64>(&pyramid(63), Config::default()).unwrap(),
+                1 << 63
+            );
+
+            let grid = pyramid(64);
+            assert!(leaves::<u64>(&grid, Config::default()).is_err());
+            assert_eq!(leaves::<u128>(&grid, Config::default()).unwrap(), 1 << 64);
+            assert_eq!(
+                leaves::<BigUint>(&grid, Config::default()).unwrap(),
+                BigUint::from(1u128 << 64)
+            );
+
+            // Timelines report the overflow instead of wrapping around
+            let grid = pyramid(70);
+            let timelines = Timelines::new(&grid);
+            assert!(timelines.count().is_err());
+            assert!(timelines.nth(0).is_err());
+            assert!(timelines.sample(&mut Rng::new(70)).is_err());
+            assert!(timelines.exit_histogram().is_err());
+            let grid = pyramid(63);
+            let timelines = Timelines::new(&grid);
+            assert_eq!(timelines.count().unwrap(), 1 << 63);
+            assert_eq!(
+                timelines.exit_histogram().unwrap().iter().sum::<u64>(),
+                1 << 63
+            );
+
+            let grid = pyramid(130);
+            assert!(leaves::<u128>(&grid, Config::default()).is_err());
+            assert_eq!(
+                leaves::<BigUint>(&grid, Config::default())
+                    .unwrap()
+                    .to_string(),
+                "1361129467683753853853498429727072845824"
+            );
+
+            assert_eq!(BigUint::from(0u64).to_string(), "0");
+            assert_eq!(BigUint::from(u128::MAX).to_string(), u128::MAX.to_string());
+            assert_eq!("u128".parse::<Precision>().unwrap(), Precision::U128);
+        }
+
+        fn manifold(lines: &[&str]) -> Grid<char> {
+            let mut grid = Grid::new(lines[0].len(), lines.len(), '.');
+            for (y, line) in lines.iter().enumerate() {
+                line.chars().enumerate().for_each(|(x, chr)| {
+                    grid.set(x, y, chr);
+                });
+            }
+            grid
+        }
+
+        #[test]
+        fn manifold_elements() {
+            // The general algorithms agree with the top down ones
+            let grid = Grid::<char>::from_file("data/sample.txt").unwrap();
+            let sample = Manifold::new(&grid, Config::default());
+            assert_eq!(sample.splits(), 21);
+            assert_eq!(sample.leaves::<u64>().unwrap(), 40);
+            assert_eq!(
+                Manifold::new(&pyramid(20), Config::default())
+                    .leaves::<u64>()
+                    .unwrap(),
+                1 << 20
+            );
+
+            // Mirrors send the beam sideways, where a splitter sends it up and down
+            let grid = manifold(&["..S..", ".....", "..\\.^", "....."]);
+            assert_eq!(splits(grid.clone(), Config::default()), 1);
+            assert_eq!(leaves::<u64>(&grid, Config::default()).unwrap(), 2);
+
+            // Absorbed beams still end a timeline
+            let grid = manifold(&[".S.", ".^.", "#.."]);
+            assert_eq!(leaves::<u64>(&grid, Config::default()).unwrap(), 2);
+
+            // Deflectors facing each other trap the beam forever
+            let grid = manifold(&["..S..", "..><.", "....."]);
+            assert_eq!(leaves::<u64>(&grid, Config::default()).unwrap(), 0);
+
+            // A splitter inside the trap lets beams out after any number of loops
+            let grid = manifold(&[".S....", ".>.^..", ".\\..<.", "......"]);
+            assert!(leaves::<u64>(&grid, Config::default()).is_err());
+
+            // Wider fan-out
+            let grid = manifold(&["...S...", ".......", "...^...", "......."]);
+            assert_eq!(leaves::<u64>(&grid, Config { fan_out: 3 }).unwrap(), 6);
+            assert_eq!(leaves::<u64>(&grid, Config { fan_out: 5 }).unwrap(), 6);
+            assert_eq!(splits(grid, Config { fan_out: 2 }), 1);
         }
 
         #[test]
@@ -93,6 +456,25 @@ mod tests {
         fn input() {
             assert_eq!(tachy_splits("data/input.txt").unwrap(), 1642);
             assert_eq!(tachy_leaves("data/input.txt").unwrap(), 47274292756692);
+            assert_eq!(
+                tachy_sweep("data/input.txt").unwrap(),
+                (1642, 47274292756692)
+            );
+            assert_eq!(
+                tachy_leaves_as("data/input.txt", Precision::Big, Config::default())
+                    .unwrap()
+                    .to_string(),
+                "47274292756692"
+            );
+
+            let grid = Grid::<char>::from_file("data/input.txt").unwrap();
+            let timelines = Timelines::new(&grid);
+            assert_eq!(timelines.count().unwrap(), 47274292756692);
+            assert_eq!(
+                timelines.exit_histogram().unwrap().iter().sum::<u64>(),
+                47274292756692
+            );
+            assert!(timelines.nth(47274292756691).unwrap().is_some());
         }
     }
 }
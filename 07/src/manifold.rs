@@ -0,0 +1,251 @@
+// Manifolds with more than just splitters. Beams can travel in any direction
+// here, so instead of sweeping the grid top to bottom, this builds the graph of
+// all beam states (position and direction) reachable from the start.
+//
+//   . S |  Empty space
+//   ^      Splitter; beams continue on both sides, perpendicular to their
+//          direction of travel (fan_out cells to each side)
+//   / \    Mirrors
+//   #      Absorber
+//   < >    Deflectors; every beam continues to the left or right
+
+use crate::count::Count;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::io::{Error, ErrorKind, Result};
+use utils::Grid;
+
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+pub enum Direction {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+impl Direction {
+    fn perpendicular(self) -> [Direction; 2] {
+        match self {
+            Direction::Up | Direction::Down => [Direction::Left, Direction::Right],
+            Direction::Left | Direction::Right => [Direction::Up, Direction::Down],
+        }
+    }
+
+    fn reflect(self, mirror: char) -> Direction {
+        match (mirror, self) {
+            ('/', Direction::Right) | ('\\', Direction::Left) => Direction::Up,
+            ('/', Direction::Left) | ('\\', Direction::Right) => Direction::Down,
+            ('/', Direction::Down) | ('\\', Direction::Up) => Direction::Left,
+            ('/', Direction::Up) | ('\\', Direction::Down) => Direction::Right,
+            _ => self,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct Config {
+    /// Number of beams a splitter emits to each side
+    pub fan_out: usize,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config { fan_out: 1 }
+    }
+}
+
+type Beam = (usize, usize, Direction);
+
+// What happens to a beam after its next step
+enum Step {
+    Beam(Beam),
+    // Left the manifold or got absorbed
+    End,
+}
+
+/// Manifold with splitters, mirrors, absorbers and deflectors
+pub struct Manifold<'a> {
+    grid: &'a Grid<char>,
+    config: Config,
+    start: Beam,
+}
+
+// Reachable beams and the steps between them, by index
+struct BeamGraph {
+    beams: Vec<Beam>,
+    next: Vec<Vec<usize>>,
+    ends: Vec<usize>,
+}
+
+/// True if the manifold only has the original elements, so the top down
+/// algorithms can be used.
+pub fn is_simple(grid: &Grid<char>, config: &Config) -> bool {
+    config.fan_out == 1
+        && grid
+            .coordinates()
+            .all(|(x, y)| matches!(grid.get(x, y), Some('.' | 'S' | '^')))
+}
+
+impl<'a> Manifold<'a> {
+    pub fn new(grid: &'a Grid<char>, config: Config) -> Self {
+        let (x, y) = grid.find('S').unwrap();
+        Manifold {
+            grid,
+            config,
+            start: (x, y, Direction::Down),
+        }
+    }
+
+    fn offset(
+        &self,
+        x: usize,
+        y: usize,
+        direction: Direction,
+        distance: usize,
+    ) -> Option<(usize, usize)> {
+        let (x, y) = match direction {
+            Direction::Up => (x, y.wrapping_sub(distance)),
+            Direction::Down => (x, y + distance),
+            Direction::Left => (x.wrapping_sub(distance), y),
+            Direction::Right => (x + distance, y),
+        };
+        (x < self.grid.width() && y < self.grid.height()).then_some((x, y))
+    }
+
+    fn steps(&self, (x, y, direction): Beam) -> Vec<Step> {
+        let Some((x, y)) = self.offset(x, y, direction, 1) else {
+            return vec![Step::End];
+        };
+
+        match self.grid.get(x, y).unwrap() {
+            '#' => vec![Step::End],
+            '<' => vec![Step::Beam((x, y, Direction::Left))],
+            '>' => vec![Step::Beam((x, y, Direction::Right))],
+            mirror @ ('/' | '\\') => vec![Step::Beam((x, y, direction.reflect(mirror)))],
+            '^' => direction
+                .perpendicular()
+                .into_iter()
+                .flat_map(|side| (1..=self.config.fan_out).map(move |distance| (side, distance)))
+                .filter_map(|(side, distance)| self.offset(x, y, side, distance))
+                .map(|(x, y)| Step::Beam((x, y, direction)))
+                .collect(),
+            _ => vec![Step::Beam((x, y, direction))],
+        }
+    }
+
+    fn graph(&self) -> BeamGraph {
+        let mut index: HashMap<Beam, usize> = HashMap::from([(self.start, 0)]);
+        let mut graph = BeamGraph {
+            beams: vec![self.start],
+            next: Vec::new(),
+            ends: Vec::new(),
+        };
+
+        // The beams vector doubles as the BFS queue
+        let mut current = 0;
+        while current < graph.beams.len() {
+            let mut next = Vec::new();
+            let mut ends = 0;
+            for step in self.steps(graph.beams[current]) {
+                match step {
+                    Step::End => ends += 1,
+                    Step::Beam(beam) => {
+                        let idx = *index.entry(beam).or_insert_with(|| {
+                            graph.beams.push(beam);
+                            graph.beams.len() - 1
+                        });
+                        next.push(idx);
+                    }
+                }
+            }
+            graph.next.push(next);
+            graph.ends.push(ends);
+            current += 1;
+        }
+        graph
+    }
+
+    /// Number of different splitters any beam reaches
+    pub fn splits(&self) -> usize {
+        self.graph()
+            .beams
+            .into_iter()
+            .filter_map(|(x, y, direction)| self.offset(x, y, direction, 1))
+            .filter(|(x, y)| self.grid.get(*x, *y) == Some('^'))
+            .collect::<HashSet<_>>()
+            .len()
+    }
+
+    /// Number of timelines, i.e. every way a beam can end (by leaving the
+    /// manifold or getting absorbed). Beams caught in a loop never end, so
+    /// they don't count. But if a beam can leave a loop, it can also go around
+    /// it any number of times first; that's an error, as is an overflow of C.
+    pub fn leaves<C: Count>(&self) -> Result<C> {
+        let graph = self.graph();
+        let count = graph.beams.len();
+
+        // Beams that can still end at all, found by walking the graph backwards
+        let mut previous: Vec<Vec<usize>> = vec![Vec::new(); count];
+        for (from, next) in graph.next.iter().enumerate() {
+            next.iter().for_each(|&to| previous[to].push(from));
+        }
+        let mut productive = vec![false; count];
+        let mut queue: VecDeque<usize> = (0..count).filter(|&idx| graph.ends[idx] > 0).collect();
+        queue.iter().for_each(|&idx| productive[idx] = true);
+        while let Some(idx) = queue.pop_front() {
+            for &from in &previous[idx] {
+                if !productive[from] {
+                    productive[from] = true;
+                    queue.push_back(from);
+                }
+            }
+        }
+
+        // Topological order of the productive beams (Kahn's algorithm); if
+        // that doesn't cover all of them, there's a loop.
+        let mut incoming = vec![0usize; count];
+        for from in (0..count).filter(|&idx| productive[idx]) {
+            for &to in graph.next[from].iter().filter(|&&to| productive[to]) {
+                incoming[to] += 1;
+            }
+        }
+        let mut order: Vec<usize> = (0..count)
+            .filter(|&idx| productive[idx] && incoming[idx] == 0)
+            .collect();
+        let mut current = 0;
+        while current < order.len() {
+            for &to in graph.next[order[current]]
+                .iter()
+                .filter(|&&to| productive[to])
+            {
+                incoming[to] -= 1;
+                if incoming[to] == 0 {
+                    order.push(to);
+                }
+            }
+            current += 1;
+        }
+        if order.len() != productive.iter().filter(|&&p| p).count() {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "infinite timelines (loop)",
+            ));
+        }
+
+        // Count backwards from the ends; None means overflow
+        let mut counts: Vec<Option<C>> = vec![Some(C::zero()); count];
+        for &idx in order.iter().rev() {
+            let ends =
+                (0..graph.ends[idx]).try_fold(C::zero(), |sum, _| sum.checked_add(&C::one()));
+            counts[idx] = ends.and_then(|ends| {
+                graph.next[idx]
+                    .iter()
+                    .filter(|&&to| productive[to])
+                    .try_fold(ends, |sum, &to| sum.checked_add(counts[to].as_ref()?))
+            });
+        }
+
+        counts[0]
+            .clone()
+            .ok_or_else(|| Error::new(ErrorKind::InvalidData, "timeline count overflow"))
+    }
+}
@@ -0,0 +1,174 @@
+use std::io::{Error, ErrorKind, Result};
+use utils::{Grid, Rng};
+
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Copy, Clone)]
+pub enum Choice {
+    Left,
+    Right,
+}
+
+/// One way a tachyon can travel through the manifold
+#[derive(Debug, PartialEq, Clone)]
+pub struct Timeline {
+    pub choices: Vec<Choice>,
+    /// Splitters passed through, in order
+    pub splitters: Vec<(usize, usize)>,
+    /// Column the beam leaves the manifold at the bottom
+    pub exit: usize,
+}
+
+/// All timelines of a manifold, without enumerating them. Timelines are
+/// ordered lexicographically by their choices, with Left before Right.
+pub struct Timelines<'a> {
+    grid: &'a Grid<char>,
+    start: (usize, usize),
+    // Row of the first splitter below every cell, if there is one
+    below: Grid<Option<usize>>,
+    // Number of timelines starting at every splitter, None if it overflows
+    counts: Grid<Option<u64>>,
+}
+
+fn overflow() -> Error {
+    Error::new(ErrorKind::InvalidData, "timeline count overflow")
+}
+
+// Both ways out of a splitter in column x; None if it's outside the grid
+fn branches(width: usize, x: usize) -> impl Iterator<Item = (Choice, Option<usize>)> {
+    [(Choice::Left, x.wrapping_sub(1)), (Choice::Right, x + 1)]
+        .into_iter()
+        .map(move |(choice, nx)| (choice, (nx < width).then_some(nx)))
+}
+
+// Number of timelines for a beam moving down from (x, y)
+fn count_from(
+    below: &Grid<Option<usize>>,
+    counts: &Grid<Option<u64>>,
+    x: usize,
+    y: usize,
+) -> Option<u64> {
+    match below.get(x, y).unwrap() {
+        Some(splitter_y) => counts.get(x, splitter_y).unwrap(),
+        None => Some(1),
+    }
+}
+
+impl<'a> Timelines<'a> {
+    pub fn new(grid: &'a Grid<char>) -> Self {
+        let start = grid.find('S').unwrap();
+        let mut below = Grid::new(grid.width(), grid.height(), None);
+        let mut counts = Grid::new(grid.width(), grid.height(), Some(0u64));
+
+        // Bottom up, so everything below a row is known when we get to it
+        for (x, y) in grid.coordinates_rev() {
+            if y + 1 < grid.height() {
+                let next = if grid.get(x, y + 1) == Some('^') {
+                    Some(y + 1)
+                } else {
+                    below.get(x, y + 1).unwrap()
+                };
+                below.set(x, y, next);
+            }
+        }
+
+        for (x, y) in grid.coordinates_rev() {
+            if grid.get(x, y) == Some('^') {
+                let count = branches(grid.width(), x)
+                    .filter_map(|(_, nx)| nx)
+                    .try_fold(0u64, |sum, nx| {
+                        sum.checked_add(count_from(&below, &counts, nx, y)?)
+                    });
+                counts.set(x, y, count);
+            }
+        }
+
+        Timelines {
+            grid,
+            start,
+            below,
+            counts,
+        }
+    }
+
+    /// Total number of timelines, an error if it doesn't fit into a u64
+    pub fn count(&self) -> Result<u64> {
+        count_from(&self.below, &self.counts, self.start.0, self.start.1).ok_or_else(overflow)
+    }
+
+    /// The k-th timeline (starting at 0), found by walking down and skipping
+    /// over all timelines of a left branch if k is beyond them. None if there
+    /// aren't that many.
+    pub fn nth(&self, mut k: u64) -> Result<Option<Timeline>> {
+        if k >= self.count()? {
+            return Ok(None);
+        }
+
+        let mut timeline = Timeline {
+            choices: Vec::new(),
+            splitters: Vec::new(),
+            exit: self.start.0,
+        };
+        let (mut x, mut y) = self.start;
+
+        while let Some(splitter_y) = self.below.get(x, y).unwrap() {
+            y = splitter_y;
+            timeline.splitters.push((x, y));
+
+            let (choice, nx) = branches(self.grid.width(), x)
+                .filter_map(|(choice, nx)| Some((choice, nx?)))
+                .find(|(_, nx)| {
+                    // Fits, as it's part of the total
+                    let count = count_from(&self.below, &self.counts, *nx, y).unwrap();
+                    if k < count {
+                        true
+                    } else {
+                        k -= count;
+                        false
+                    }
+                })
+                .unwrap();
+
+            timeline.choices.push(choice);
+            x = nx;
+        }
+
+        timeline.exit = x;
+        Ok(Some(timeline))
+    }
+
+    /// All timelines in lexicographic order
+    pub fn iter(&self) -> Result<impl Iterator<Item = Timeline> + '_> {
+        Ok((0..self.count()?).map_while(|k| self.nth(k).ok().flatten()))
+    }
+
+    /// A random timeline, every one of them equally likely
+    pub fn sample(&self, rng: &mut Rng) -> Result<Option<Timeline>> {
+        match self.count()? {
+            0 => Ok(None),
+            count => self.nth(rng.range(0..count)),
+        }
+    }
+
+    /// Number of timelines leaving the manifold at every column
+    pub fn exit_histogram(&self) -> Result<Vec<u64>> {
+        let width = self.grid.width();
+        let mut beams = vec![0u64; width];
+        beams[self.start.0] = 1;
+
+        for y in self.start.1 + 1..self.grid.height() {
+            let splitters: Vec<usize> = (0..width)
+                .filter(|x| self.grid.get(*x, y) == Some('^'))
+                .collect();
+            // Beams hitting a splitter stop there, before any of them branch
+            // out, as a splitter next to it may send beams into its column
+            let mut next = beams.clone();
+            splitters.iter().for_each(|&x| next[x] = 0);
+            for &x in &splitters {
+                for nx in branches(width, x).filter_map(|(_, nx)| nx) {
+                    next[nx] = next[nx].checked_add(beams[x]).ok_or_else(overflow)?;
+                }
+            }
+            beams = next;
+        }
+        Ok(beams)
+    }
+}
@@ -0,0 +1,183 @@
+use crate::config::Metric;
+use crate::{Link, Point, links};
+use std::collections::BTreeMap;
+use std::io::{Error, ErrorKind, Result, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use utils::DisjointSet;
+
+/// Where to write the analytics to
+#[derive(Debug, PartialEq, Clone)]
+pub enum Export {
+    /// Only the answers
+    None,
+    /// Spanning tree as a Graphviz graph
+    Dot(PathBuf),
+    /// histogram.csv and tree.csv in a directory
+    Csv(PathBuf),
+}
+
+/// Everything about how the circuits form
+#[derive(Debug, PartialEq)]
+pub struct Analytics {
+    /// After each of the first connections: how many circuits there are of
+    /// every size, largest first
+    pub histograms: Vec<Vec<(usize, usize)>>,
+    /// Minimum spanning tree, in the order the links are added
+    pub tree: Vec<Link>,
+    /// The link that connects everything into one circuit, if there is one
+    pub completing: Option<Link>,
+    pub metric: Metric,
+}
+
+impl Analytics {
+    /// Product of the sizes of the k largest circuits after all connections
+    pub fn largest_product(&self, k: usize) -> usize {
+        let Some(histogram) = self.histograms.last() else {
+            // Only single boxes
+            return 1;
+        };
+        histogram
+            .iter()
+            .flat_map(|&(size, count)| std::iter::repeat_n(size, count))
+            .take(k)
+            .product()
+    }
+}
+
+pub fn analyze(points: &[Point], first_n_count: usize, metric: Metric) -> Analytics {
+    let mut circuits = DisjointSet::new(points.len());
+    // Circuit size -> number of circuits that size
+    let mut sizes: BTreeMap<usize, usize> = BTreeMap::new();
+    if !points.is_empty() {
+        sizes.insert(1, points.len());
+    }
+
+    let mut histograms = Vec::new();
+    for link in links::shortest_links(points, first_n_count, metric) {
+        let (first, second) = (circuits.size(link.from), circuits.size(link.to));
+        if circuits.union(link.from, link.to) {
+            for size in [first, second] {
+                let count = sizes.get_mut(&size).unwrap();
+                *count -= 1;
+                if *count == 0 {
+                    sizes.remove(&size);
+                }
+            }
+            *sizes.entry(first + second).or_default() += 1;
+        }
+        histograms.push(
+            sizes
+                .iter()
+                .rev()
+                .map(|(&size, &count)| (size, count))
+                .collect(),
+        );
+    }
+
+    let tree = links::spanning_tree(points, metric);
+    Analytics {
+        histograms,
+        completing: tree.last().copied(),
+        tree,
+        metric,
+    }
+}
+
+/// Writes the spanning tree as an undirected Graphviz graph, with the link
+/// lengths as labels and the completing link in red.
+pub fn write_dot<W: Write>(points: &[Point], analytics: &Analytics, out: &mut W) -> Result<()> {
+    writeln!(out, "graph circuits {{")?;
+    for (idx, point) in points.iter().enumerate() {
+        writeln!(
+            out,
+            "  {idx} [label=\"{},{},{}\"];",
+            point.x, point.y, point.z
+        )?;
+    }
+    for link in &analytics.tree {
+        let color = if Some(*link) == analytics.completing {
+            ", color=red"
+        } else {
+            ""
+        };
+        writeln!(
+            out,
+            "  {} -- {} [label=\"{:.2}\"{color}];",
+            link.from,
+            link.to,
+            link.length(analytics.metric)
+        )?;
+    }
+    writeln!(out, "}}")
+}
+
+/// One line per connection and circuit size
+pub fn write_histogram_csv<W: Write>(analytics: &Analytics, out: &mut W) -> Result<()> {
+    writeln!(out, "connection,size,count")?;
+    for (connection, histogram) in analytics.histograms.iter().enumerate() {
+        for (size, count) in histogram {
+            writeln!(out, "{},{size},{count}", connection + 1)?;
+        }
+    }
+    Ok(())
+}
+
+/// One line per spanning tree link, with both ends and the length
+pub fn write_tree_csv<W: Write>(
+    points: &[Point],
+    analytics: &Analytics,
+    out: &mut W,
+) -> Result<()> {
+    writeln!(out, "from,to,from_x,from_y,from_z,to_x,to_y,to_z,distance")?;
+    for link in &analytics.tree {
+        let (from, to) = (points[link.from], points[link.to]);
+        writeln!(
+            out,
+            "{},{},{},{},{},{},{},{},{}",
+            link.from,
+            link.to,
+            from.x,
+            from.y,
+            from.z,
+            to.x,
+            to.y,
+            to.z,
+            link.length(analytics.metric)
+        )?;
+    }
+    Ok(())
+}
+
+fn create<P: AsRef<Path>>(path: P) -> Result<std::io::BufWriter<std::fs::File>> {
+    Ok(std::io::BufWriter::new(std::fs::File::create(path)?))
+}
+
+pub fn export(points: &[Point], analytics: &Analytics, export: &Export) -> Result<()> {
+    match export {
+        Export::None => Ok(()),
+        Export::Dot(path) => write_dot(points, analytics, &mut create(path)?),
+        Export::Csv(dir) => {
+            std::fs::create_dir_all(dir)?;
+            write_histogram_csv(analytics, &mut create(dir.join("histogram.csv"))?)?;
+            write_tree_csv(points, analytics, &mut create(dir.join("tree.csv"))?)
+        }
+    }
+}
+
+impl FromStr for Export {
+    type Err = Error;
+
+    /// Parses "none", "dot:FILE" or "csv:DIR"
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.split_once(':') {
+            None if s == "none" => Ok(Export::None),
+            Some(("dot", path)) if !path.is_empty() => Ok(Export::Dot(path.into())),
+            Some(("csv", dir)) if !dir.is_empty() => Ok(Export::Csv(dir.into())),
+            _ => Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("invalid export '{s}'"),
+            )),
+        }
+    }
+}
@@ -0,0 +1,117 @@
+use crate::{Link, Point};
+use std::io::{Error, ErrorKind};
+use std::str::FromStr;
+
+/// How to measure the distance between junction boxes
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum Metric {
+    Euclidean,
+    SquaredEuclidean,
+    Manhattan,
+    Chebyshev,
+}
+
+/// What to answer with, given the link that completes the circuit
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum Formula {
+    /// Product of one coordinate (0 = x, 1 = y, 2 = z) of both ends
+    Product(usize),
+    /// Length of the link
+    Length,
+}
+
+/// Answer from a formula; products stay exact
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum Answer {
+    Product(u128),
+    Length(f64),
+}
+
+impl std::fmt::Display for Answer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Answer::Product(product) => write!(f, "{product}"),
+            Answer::Length(length) => write!(f, "{length}"),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct Config {
+    pub metric: Metric,
+    /// Number of largest circuits to multiply
+    pub top_k: usize,
+    pub formula: Formula,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            metric: Metric::Euclidean,
+            top_k: 3,
+            formula: Formula::Product(0),
+        }
+    }
+}
+
+impl Metric {
+    /// Exact value to order links by, from the distance along every axis.
+    /// That's the squared length for Euclidean, so it stays an integer.
+    pub fn measure(&self, gaps: [usize; 3]) -> u128 {
+        let gaps = gaps.map(|gap| gap as u128);
+        match self {
+            Metric::Euclidean | Metric::SquaredEuclidean => gaps.iter().map(|gap| gap * gap).sum(),
+            Metric::Manhattan => gaps.iter().sum(),
+            Metric::Chebyshev => gaps.into_iter().max().unwrap(),
+        }
+    }
+
+    /// Length of a link from its measure
+    pub fn length(&self, measure: u128) -> f64 {
+        match self {
+            Metric::Euclidean => (measure as f64).sqrt(),
+            _ => measure as f64,
+        }
+    }
+}
+
+impl Formula {
+    pub fn evaluate(&self, points: &[Point], link: &Link, metric: Metric) -> Answer {
+        match self {
+            Formula::Product(axis) => Answer::Product(
+                points[link.from].axis(*axis) as u128 * points[link.to].axis(*axis) as u128,
+            ),
+            Formula::Length => Answer::Length(link.length(metric)),
+        }
+    }
+}
+
+impl FromStr for Config {
+    type Err = Error;
+
+    /// Parses a space separated list of
+    /// "metric:euclidean|squared|manhattan|chebyshev", "top:K" and
+    /// "answer:x|y|z|length"; missing parts are defaults.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid =
+            |part: &str| Error::new(ErrorKind::InvalidInput, format!("invalid option '{part}'"));
+        let mut config = Config::default();
+
+        for part in s.split_whitespace() {
+            match part.split_once(':').ok_or_else(|| invalid(part))? {
+                ("metric", "euclidean") => config.metric = Metric::Euclidean,
+                ("metric", "squared") => config.metric = Metric::SquaredEuclidean,
+                ("metric", "manhattan") => config.metric = Metric::Manhattan,
+                ("metric", "chebyshev") => config.metric = Metric::Chebyshev,
+                ("top", k) => config.top_k = k.parse().map_err(|_| invalid(part))?,
+                ("answer", "x") => config.formula = Formula::Product(0),
+                ("answer", "y") => config.formula = Formula::Product(1),
+                ("answer", "z") => config.formula = Formula::Product(2),
+                ("answer", "length") => config.formula = Formula::Length,
+                _ => return Err(invalid(part)),
+            }
+        }
+
+        Ok(config)
+    }
+}
@@ -0,0 +1,166 @@
+use crate::config::Metric;
+use crate::{Link, Point};
+
+// Subtrees with at most this many points aren't split any further
+const LEAF_SIZE: usize = 8;
+
+struct Node {
+    // Range of the tree's point order covered by this node
+    start: usize,
+    end: usize,
+    // Bounding box of those points
+    min: [usize; 3],
+    max: [usize; 3],
+    children: Option<(usize, usize)>,
+    // Set if all points of the subtree are in the same component
+    component: Option<usize>,
+}
+
+/// Static k-d tree for finding the nearest point of another component, which
+/// is all Borůvka's algorithm needs.
+pub struct KdTree<'a> {
+    points: &'a [Point],
+    metric: Metric,
+    order: Vec<usize>,
+    nodes: Vec<Node>,
+}
+
+impl<'a> KdTree<'a> {
+    pub fn new(points: &'a [Point], metric: Metric) -> Self {
+        let mut tree = KdTree {
+            points,
+            metric,
+            order: (0..points.len()).collect(),
+            nodes: Vec::new(),
+        };
+        if !points.is_empty() {
+            tree.build(0, points.len());
+        }
+        tree
+    }
+
+    // Builds the subtree for order[start..end] and returns its node index
+    fn build(&mut self, start: usize, end: usize) -> usize {
+        let mut min = [usize::MAX; 3];
+        let mut max = [0; 3];
+        for &idx in &self.order[start..end] {
+            for axis in 0..3 {
+                min[axis] = min[axis].min(self.points[idx].axis(axis));
+                max[axis] = max[axis].max(self.points[idx].axis(axis));
+            }
+        }
+
+        let node = self.nodes.len();
+        self.nodes.push(Node {
+            start,
+            end,
+            min,
+            max,
+            children: None,
+            component: None,
+        });
+
+        if end - start > LEAF_SIZE {
+            // Split at the median of the widest axis
+            let axis = (0..3).max_by_key(|&axis| max[axis] - min[axis]).unwrap();
+            let mid = (start + end) / 2;
+            let points = self.points;
+            self.order[start..end]
+                .select_nth_unstable_by_key(mid - start, |&idx| points[idx].axis(axis));
+            let left = self.build(start, mid);
+            let right = self.build(mid, end);
+            self.nodes[node].children = Some((left, right));
+        }
+        node
+    }
+
+    /// Marks the subtrees whose points are all in one component, bottom up
+    /// (children are always after their parent).
+    pub fn label(&mut self, component: &[usize]) {
+        for node in (0..self.nodes.len()).rev() {
+            let label = match self.nodes[node].children {
+                Some((left, right)) => {
+                    match (self.nodes[left].component, self.nodes[right].component) {
+                        (Some(left), Some(right)) if left == right => Some(left),
+                        _ => None,
+                    }
+                }
+                None => {
+                    let Node { start, end, .. } = self.nodes[node];
+                    let first = component[self.order[start]];
+                    self.order[start..end]
+                        .iter()
+                        .all(|&idx| component[idx] == first)
+                        .then_some(first)
+                }
+            };
+            self.nodes[node].component = label;
+        }
+    }
+
+    // Smallest measure from the point to the node's bounding box
+    fn box_distance(&self, node: &Node, point: &Point) -> u128 {
+        let gaps: [usize; 3] = std::array::from_fn(|axis| {
+            let value = point.axis(axis);
+            if value < node.min[axis] {
+                node.min[axis] - value
+            } else {
+                value.saturating_sub(node.max[axis])
+            }
+        });
+        self.metric.measure(gaps)
+    }
+
+    /// Nearest point (by link order) to `from` that's in another component,
+    /// unless `best` is already closer. Needs `label` first.
+    pub fn nearest_other(
+        &self,
+        from: usize,
+        component: &[usize],
+        best: Option<Link>,
+    ) -> Option<Link> {
+        let mut best = best;
+        if !self.nodes.is_empty() {
+            self.search(0, from, component, &mut best);
+        }
+        best
+    }
+
+    fn search(&self, node: usize, from: usize, component: &[usize], best: &mut Option<Link>) {
+        let current = &self.nodes[node];
+        if current.component == Some(component[from]) {
+            return;
+        }
+        if let Some(best) = best
+            && self.box_distance(current, &self.points[from]) > best.measure
+        {
+            return;
+        }
+
+        match current.children {
+            Some((left, right)) => {
+                // Closer child first, so the other one is more likely pruned
+                let point = &self.points[from];
+                let (first, second) = if self.box_distance(&self.nodes[left], point)
+                    <= self.box_distance(&self.nodes[right], point)
+                {
+                    (left, right)
+                } else {
+                    (right, left)
+                };
+                self.search(first, from, component, best);
+                self.search(second, from, component, best);
+            }
+            None => {
+                for &to in &self.order[current.start..current.end] {
+                    if component[to] != component[from] {
+                        let link = Link::new(self.points, from, to, self.metric);
+                        if best.is_none_or(|best| link < best) {
+                            *best = Some(link);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
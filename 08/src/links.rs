@@ -0,0 +1,117 @@
+use crate::config::Metric;
+use crate::kdtree::KdTree;
+use crate::{Link, Point};
+use std::collections::HashMap;
+use utils::DisjointSet;
+
+// All links no longer than `radius`, found with a spatial hash with
+// cells of that size; only the neighboring cells need to be checked. That
+// works for every metric, as none is shorter than the distance along an axis.
+fn links_within(points: &[Point], radius: usize, metric: Metric) -> Vec<Link> {
+    let limit = metric.measure([radius, 0, 0]);
+    let cell = |point: &Point| (point.x / radius, point.y / radius, point.z / radius);
+    let mut cells: HashMap<(usize, usize, usize), Vec<usize>> = HashMap::new();
+    for (idx, point) in points.iter().enumerate() {
+        cells.entry(cell(point)).or_default().push(idx);
+    }
+
+    let mut links = Vec::new();
+    for (from, point) in points.iter().enumerate() {
+        let (cx, cy, cz) = cell(point);
+        for x in cx.saturating_sub(1)..=cx + 1 {
+            for y in cy.saturating_sub(1)..=cy + 1 {
+                for z in cz.saturating_sub(1)..=cz + 1 {
+                    let Some(others) = cells.get(&(x, y, z)) else {
+                        continue;
+                    };
+                    links.extend(
+                        others
+                            .iter()
+                            .filter(|&&to| to > from)
+                            .map(|&to| Link::new(points, from, to, metric))
+                            .filter(|link| link.measure <= limit),
+                    );
+                }
+            }
+        }
+    }
+    links
+}
+
+// Largest radius (along one axis) whose measure isn't above the given one
+fn radius(measure: u128, metric: Metric) -> usize {
+    match metric {
+        Metric::Euclidean | Metric::SquaredEuclidean => (measure as f64).sqrt() as usize,
+        Metric::Manhattan | Metric::Chebyshev => measure as usize,
+    }
+}
+
+/// Radius to start looking for the n shortest links at, never longer than the
+/// n-th of them: those have at least k ends with k(k - 1) / 2 >= n, and every
+/// one of those has its nearest neighbor at most that far away.
+pub fn first_radius(points: &[Point], n: usize, metric: Metric) -> usize {
+    let alone: Vec<usize> = (0..points.len()).collect();
+    let mut tree = KdTree::new(points, metric);
+    tree.label(&alone);
+    let mut nearest: Vec<u128> = (0..points.len())
+        .filter_map(|from| tree.nearest_other(from, &alone, None))
+        .map(|link| link.measure)
+        .collect();
+
+    let ends = (2..).find(|k| k * (k - 1) / 2 >= n).unwrap();
+    let (_, measure, _) = nearest.select_nth_unstable(ends - 1);
+    radius(*measure, metric).max(1)
+}
+
+/// The n shortest links, shortest first, without computing all pairs. Only
+/// links up to a certain length are collected, and that length doubles until
+/// there are enough of them, starting from a lower bound so clusters don't
+/// make the first guess cover (almost) all pairs.
+pub fn shortest_links(points: &[Point], n: usize, metric: Metric) -> Vec<Link> {
+    let n = n.min(points.len() * points.len().saturating_sub(1) / 2);
+    if n == 0 {
+        return Vec::new();
+    }
+
+    let mut radius = first_radius(points, n, metric);
+    loop {
+        let mut links = links_within(points, radius, metric);
+        if links.len() >= n {
+            if links.len() > n {
+                links.select_nth_unstable(n);
+                links.truncate(n);
+            }
+            links.sort_unstable();
+            return links;
+        }
+        radius = radius.saturating_mul(2);
+    }
+}
+
+/// Euclidean minimum spanning tree with Borůvka's algorithm: in every round,
+/// each circuit is connected to its nearest other circuit. The links come out
+/// in the order Kruskal would add them, so the last one completes the circuit.
+pub fn spanning_tree(points: &[Point], metric: Metric) -> Vec<Link> {
+    let mut tree = KdTree::new(points, metric);
+    let mut circuits = DisjointSet::new(points.len());
+    let mut links = Vec::new();
+
+    while circuits.components() > 1 {
+        let component: Vec<usize> = (0..points.len()).map(|idx| circuits.find(idx)).collect();
+        tree.label(&component);
+
+        let mut cheapest = vec![None; points.len()];
+        for (from, &circuit) in component.iter().enumerate() {
+            cheapest[circuit] = tree.nearest_other(from, &component, cheapest[circuit]);
+        }
+        // Links never compare equal, so these never form a cycle
+        for link in cheapest.into_iter().flatten() {
+            if circuits.union(link.from, link.to) {
+                links.push(link);
+            }
+        }
+    }
+
+    links.sort_unstable();
+    links
+}
@@ -1,9 +1,16 @@
 #![allow(dead_code)]
 
-use std::collections::HashMap;
+mod analytics;
+mod config;
+mod kdtree;
+mod links;
+
+use analytics::{Analytics, Export};
+use config::{Answer, Config, Metric};
 use std::hash::Hash;
-use std::io::Result;
+use std::io::{Error, ErrorKind, Result};
 use std::path::Path;
+use utils::DisjointSet;
 
 #[derive(Debug, Eq, PartialEq, Copy, Clone, Hash)]
 struct Point {
@@ -12,24 +19,51 @@ struct Point {
     pub z: usize,
 }
 
-#[derive(Debug, PartialEq)]
+/// Connection between two points, by their index (from < to). Links are
+/// ordered by their exact measure (see `Metric::measure`) and then by the
+/// indices, so two different links never compare equal.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Copy, Clone)]
 struct Link {
-    pub from: Point,
-    pub to: Point,
-    pub distance: usize,
+    pub measure: u128,
+    pub from: usize,
+    pub to: usize,
 }
 
 impl Point {
-    fn distance_to(&self, other: &Point) -> usize {
-        (self.x.abs_diff(other.x).pow(2)
-            + self.y.abs_diff(other.y).pow(2)
-            + self.z.abs_diff(other.z).pow(2))
-        .isqrt()
+    /// Distance along every axis
+    fn gaps(&self, other: &Point) -> [usize; 3] {
+        [
+            self.x.abs_diff(other.x),
+            self.y.abs_diff(other.y),
+            self.z.abs_diff(other.z),
+        ]
+    }
+
+    fn axis(&self, axis: usize) -> usize {
+        [self.x, self.y, self.z][axis]
+    }
+}
+
+impl Link {
+    fn new(points: &[Point], first: usize, second: usize, metric: Metric) -> Self {
+        Link {
+            measure: metric.measure(points[first].gaps(&points[second])),
+            from: first.min(second),
+            to: first.max(second),
+        }
+    }
+
+    /// Length of the link; only for display, links are compared exactly
+    fn length(&self, metric: Metric) -> f64 {
+        metric.length(self.measure)
     }
 }
 
+/// Largest coordinate that keeps every squared distance within a u128
+const MAX_COORDINATE: usize = (u128::MAX / 3).isqrt() as usize;
+
 fn read_points<P: AsRef<Path>>(path: P) -> Result<Vec<Point>> {
-    Ok(std::fs::read_to_string(path)?
+    let points: Vec<Point> = std::fs::read_to_string(path)?
         .lines()
         .map(|line| {
             let pt: Vec<usize> = line
@@ -43,98 +77,435 @@ fn read_points<P: AsRef<Path>>(path: P) -> Result<Vec<Point>> {
                 z: pt[2],
             }
         })
-        .collect())
+        .collect();
+
+    match points
+        .iter()
+        .find(|point| (0..3).any(|axis| point.axis(axis) > MAX_COORDINATE))
+    {
+        Some(point) => Err(Error::new(
+            ErrorKind::InvalidData,
+            format!("{point:?} is too far out, coordinates go up to {MAX_COORDINATE}"),
+        )),
+        None => Ok(points),
+    }
 }
 
-fn distances_sorted(points: &[Point]) -> Vec<Link> {
+fn distances_sorted(points: &[Point], metric: Metric) -> Vec<Link> {
     let mut distances: Vec<Link> = (0..points.len() - 1)
         .flat_map(|i| (i + 1..points.len()).map(move |j| (i, j)))
-        .map(|(from, to)| Link {
-            from: points[from],
-            to: points[to],
-            distance: points[from].distance_to(&points[to]),
-        })
+        .map(|(from, to)| Link::new(points, from, to, metric))
         .collect();
-    distances.sort_by(|a, b| a.distance.cmp(&b.distance));
+    distances.sort_unstable();
     distances
 }
 
-fn lowest_first(first: usize, second: usize) -> (usize, usize) {
-    (std::cmp::min(first, second), std::cmp::max(first, second))
-}
-
-fn circuit_index(points: &[Point]) -> (HashMap<Point, usize>, Vec<Vec<Point>>) {
-    let mut index = HashMap::new();
-    let mut circuits: Vec<Vec<Point>> = Vec::new();
-
-    for point in points.iter() {
-        index.insert(*point, circuits.len());
-        circuits.push(vec![*point]);
-    }
-
-    (index, circuits)
-}
-
-fn combine_circuits(
-    index: &mut HashMap<Point, usize>,
-    circuits: &mut [Vec<Point>],
-    first: Point,
-    second: Point,
-) {
-    // We're always combining circuits "down" to make sure the largest circuit
-    // will form at index 0 ...
-    let (to, from) = lowest_first(index[&first], index[&second]);
-    if from != to {
-        index
-            .iter_mut()
-            .filter(|(_, v)| **v == from)
-            .for_each(|(_, v)| *v = to);
-
-        let from_items = std::mem::take(&mut circuits[from]);
-        circuits[to].extend(from_items);
-    }
-}
-
-fn largest_circuits_product(circuits: &[Vec<Point>]) -> usize {
-    let mut circuit_length: Vec<usize> = circuits.iter().map(|circuit| circuit.len()).collect();
-    circuit_length.sort();
-    circuit_length.into_iter().rev().take(3).product::<usize>()
+fn largest_circuits_product(circuits: &DisjointSet, top_k: usize) -> usize {
+    circuits.largest(top_k).into_iter().product()
 }
 
 fn junctions_complete(points: Vec<Point>, first_n_count: usize) -> (usize, usize) {
-    let points_count = points.len();
-    let (mut index, mut circuits) = circuit_index(&points);
+    let mut circuits = DisjointSet::new(points.len());
     let mut first_n = 0;
 
-    for (count, link) in distances_sorted(&points).into_iter().enumerate() {
-        combine_circuits(&mut index, &mut circuits, link.from, link.to);
+    for (count, link) in distances_sorted(&points, Metric::Euclidean)
+        .into_iter()
+        .enumerate()
+    {
+        circuits.union(link.from, link.to);
 
         if count == first_n_count - 1 {
-            first_n = largest_circuits_product(&circuits);
+            first_n = largest_circuits_product(&circuits, 3);
         }
 
-        if circuits[0].len() == points_count {
-            return (first_n, link.from.x * link.to.x);
+        if circuits.components() == 1 {
+            return (first_n, points[link.from].x * points[link.to].x);
         }
     }
 
     unreachable!();
 }
 
-fn main() {
-    println!("Run 'cargo test' instead ;)");
+/// Same answers as `junctions_complete` (with the default config), for lots
+/// of points: never looks at more links than needed for the first n, and
+/// completes the circuit with a minimum spanning tree instead of going
+/// through all links. There's no answer without at least two points.
+fn junctions_complete_fast(
+    points: &[Point],
+    first_n_count: usize,
+    config: &Config,
+) -> (usize, Option<Answer>) {
+    let mut circuits = DisjointSet::new(points.len());
+    for link in links::shortest_links(points, first_n_count, config.metric) {
+        circuits.union(link.from, link.to);
+    }
+
+    let last = links::spanning_tree(points, config.metric).pop();
+    (
+        largest_circuits_product(&circuits, config.top_k),
+        last.map(|last| config.formula.evaluate(points, &last, config.metric)),
+    )
+}
+
+// Circuit sizes after the links, counting only those actually made: there
+// may be fewer pairs of junction boxes than connections asked for
+fn circuits_summary(analytics: &Analytics, connections: usize) -> Option<String> {
+    let histogram = analytics.histograms.last()?;
+    let sizes: Vec<String> = histogram
+        .iter()
+        .map(|(size, count)| format!("{count}x{size}"))
+        .collect();
+    let used = analytics.histograms.len();
+    let label = if used < connections {
+        format!("{used} links (of {connections} asked for)")
+    } else {
+        format!("{used} links")
+    };
+    Some(format!("Circuits after {label}: {}", sizes.join(" ")))
+}
+
+fn main() -> Result<()> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.len() < 2 {
+        println!("Run 'cargo test' instead ;)");
+        println!(
+            "Or: day_08 <file> <connections> [metric:euclidean|squared|manhattan|chebyshev] \
+             [top:K] [answer:x|y|z|length] [dot:FILE|csv:DIR]"
+        );
+        return Ok(());
+    }
+
+    let connections: usize = args[1]
+        .parse()
+        .map_err(|_| Error::new(ErrorKind::InvalidInput, "invalid connection count"))?;
+    let (exports, options): (Vec<&String>, Vec<&String>) = args[2..]
+        .iter()
+        .partition(|arg| arg.starts_with("dot:") || arg.starts_with("csv:"));
+    let export: Export = match exports.last() {
+        Some(export) => export.parse()?,
+        None => Export::None,
+    };
+    let config: Config = options
+        .iter()
+        .map(|option| option.as_str())
+        .collect::<Vec<_>>()
+        .join(" ")
+        .parse()?;
+
+    let points = read_points(&args[0])?;
+    let analytics = analytics::analyze(&points, connections, config.metric);
+    if let Some(circuits) = circuits_summary(&analytics, connections) {
+        println!("{circuits}");
+    }
+    println!(
+        "Largest {} circuits product: {}",
+        config.top_k,
+        analytics.largest_product(config.top_k)
+    );
+    if let Some(link) = analytics.completing {
+        let (from, to) = (points[link.from], points[link.to]);
+        println!(
+            "Completing link: {from:?} -- {to:?} ({:.3})",
+            link.length(config.metric)
+        );
+        println!(
+            "Answer: {}",
+            config.formula.evaluate(&points, &link, config.metric)
+        );
+    }
+    analytics::export(&points, &analytics, &export)
 }
 
 #[cfg(test)]
 mod tests {
     mod day08_playground {
         use super::super::*;
+        use utils::Rng;
 
         #[test]
         fn sample() {
             let (first_n, all) = junctions_complete(read_points("data/sample.txt").unwrap(), 10);
             assert_eq!(first_n, 40);
             assert_eq!(all, 25272);
+
+            let points = read_points("data/sample.txt").unwrap();
+            assert_eq!(
+                junctions_complete_fast(&points, 10, &Config::default()),
+                (40, Some(Answer::Product(25272)))
+            );
+        }
+
+        fn random_points(rng: &mut Rng, count: usize, size: u64) -> Vec<Point> {
+            (0..count)
+                .map(|_| Point {
+                    x: rng.range(0..size) as usize,
+                    y: rng.range(0..size) as usize,
+                    z: rng.range(0..size) as usize,
+                })
+                .collect()
+        }
+
+        // Kruskal over all links, by squared distance and then indices
+        fn exact_links(points: &[Point], metric: Metric) -> Vec<Link> {
+            let mut links: Vec<_> = (0..points.len())
+                .flat_map(|from| (from + 1..points.len()).map(move |to| (from, to)))
+                .map(|(from, to)| Link::new(points, from, to, metric))
+                .collect();
+            links.sort();
+            links
+        }
+
+        #[test]
+        fn sample_analytics() {
+            let points = read_points("data/sample.txt").unwrap();
+            let analytics = analytics::analyze(&points, 10, Metric::Euclidean);
+
+            assert_eq!(analytics.histograms.len(), 10);
+            assert_eq!(analytics.histograms[0], [(2, 1), (1, 18)]);
+            // The fourth link connects two boxes already in the same circuit
+            assert_eq!(analytics.histograms[2], [(3, 1), (2, 1), (1, 15)]);
+            assert_eq!(analytics.histograms[3], analytics.histograms[2]);
+            assert_eq!(analytics.histograms[9], [(5, 1), (4, 1), (2, 2), (1, 7)]);
+            assert!(analytics.histograms.iter().all(|histogram| {
+                histogram
+                    .iter()
+                    .map(|(size, count)| size * count)
+                    .sum::<usize>()
+                    == 20
+            }));
+
+            assert_eq!(
+                circuits_summary(&analytics, 10).unwrap(),
+                "Circuits after 10 links: 1x5 1x4 2x2 7x1"
+            );
+            let all = analytics::analyze(&points, 1000, Metric::Euclidean);
+            assert_eq!(
+                circuits_summary(&all, 1000).unwrap(),
+                "Circuits after 190 links (of 1000 asked for): 1x20"
+            );
+            assert_eq!(analytics.largest_product(3), 40);
+            assert_eq!(analytics.largest_product(5), 5 * 4 * 2 * 2);
+            assert_eq!(analytics.tree.len(), 19);
+            let link = analytics.completing.unwrap();
+            assert_eq!(points[link.from].x * points[link.to].x, 25272);
+
+            let mut csv = Vec::new();
+            analytics::write_histogram_csv(&analytics, &mut csv).unwrap();
+            let csv = String::from_utf8(csv).unwrap();
+            assert!(csv.starts_with("connection,size,count\n1,2,1\n1,1,18\n2,"));
+
+            let mut csv = Vec::new();
+            analytics::write_tree_csv(&points, &analytics, &mut csv).unwrap();
+            assert_eq!(String::from_utf8(csv).unwrap().lines().count(), 20);
+
+            let mut dot = Vec::new();
+            analytics::write_dot(&points, &analytics, &mut dot).unwrap();
+            let dot = String::from_utf8(dot).unwrap();
+            assert!(dot.starts_with("graph circuits {\n  0 [label=\"162,817,812\"];\n"));
+            assert_eq!(dot.matches(" -- ").count(), 19);
+            assert_eq!(dot.matches("color=red").count(), 1);
+            assert!(dot.ends_with("}\n"));
+
+            assert_eq!(
+                "dot:out.dot".parse::<Export>().unwrap(),
+                Export::Dot("out.dot".into())
+            );
+            assert_eq!(
+                "csv:out".parse::<Export>().unwrap(),
+                Export::Csv("out".into())
+            );
+            assert!("csv:".parse::<Export>().is_err());
+        }
+
+        #[test]
+        fn configs() {
+            let points = read_points("data/sample.txt").unwrap();
+            let answer =
+                |config: &str| junctions_complete_fast(&points, 10, &config.parse().unwrap());
+            let product = |product| Some(Answer::Product(product));
+            assert_eq!(answer(""), (40, product(25272)));
+            assert_eq!(answer("top:2"), (20, product(25272)));
+            assert_eq!(answer("top:100").0, 5 * 4 * 2 * 2);
+            assert_eq!(answer("answer:y"), (40, product(146 * 168)));
+            assert_eq!(answer("answer:z"), (40, product(977 * 530)));
+
+            // The order of the links, and so the circuits, only depends on
+            // the metric; Euclidean and squared Euclidean are the same apart
+            // from the length.
+            let (first_n, Some(Answer::Length(length))) = answer("answer:length") else {
+                panic!("expected a length");
+            };
+            assert_eq!(
+                answer("metric:squared answer:length"),
+                (first_n, Some(Answer::Length(length * length)))
+            );
+            assert!((length - 458.360).abs() < 0.001);
+
+            for metric in ["manhattan", "chebyshev"] {
+                let config: Config = format!("metric:{metric} answer:length").parse().unwrap();
+                let all = {
+                    let mut links = distances_sorted(&points, config.metric);
+                    let mut circuits = DisjointSet::new(points.len());
+                    links.retain(|link| circuits.union(link.from, link.to));
+                    links
+                };
+                let (_, length) = junctions_complete_fast(&points, 10, &config);
+                assert_eq!(
+                    length,
+                    Some(Answer::Length(all.last().unwrap().measure as f64))
+                );
+            }
+
+            // Products are exact beyond 2^53, and it takes two to link
+            let far =
+                [(4_000_000_001, 0, 0), (4_000_000_003, 1, 0)].map(|(x, y, z)| Point { x, y, z });
+            assert_eq!(
+                junctions_complete_fast(&far, 1, &Config::default()).1,
+                product(4_000_000_001 * 4_000_000_003)
+            );
+            assert_eq!(
+                junctions_complete_fast(&far[..1], 0, &Config::default()).1,
+                None
+            );
+
+            assert!("metric:taxicab".parse::<Config>().is_err());
+            assert!("top:x".parse::<Config>().is_err());
+            assert!("answer:w".parse::<Config>().is_err());
+        }
+
+        #[test]
+        fn exact_order() {
+            // Squared lengths 4 and 5 used to both round down to 2, and ties go
+            // to the lower indices.
+            let points =
+                [(0, 0, 0), (2, 1, 0), (0, 2, 0), (2, 0, 0)].map(|(x, y, z)| Point { x, y, z });
+            let order: Vec<(usize, usize)> = distances_sorted(&points, Metric::Euclidean)
+                .iter()
+                .map(|link| (link.from, link.to))
+                .collect();
+            assert_eq!(order, [(1, 3), (0, 2), (0, 3), (0, 1), (1, 2), (2, 3)]);
+            assert_eq!(
+                Link::new(&points, 3, 0, Metric::Euclidean),
+                Link::new(&points, 0, 3, Metric::Euclidean)
+            );
+            assert_eq!(
+                Link::new(&points, 0, 1, Metric::Euclidean).length(Metric::Euclidean),
+                5f64.sqrt()
+            );
+
+            // The farthest apart boxes don't overflow, anything beyond that
+            // is rejected when reading
+            let far = [
+                Point { x: 0, y: 0, z: 0 },
+                Point {
+                    x: MAX_COORDINATE,
+                    y: MAX_COORDINATE,
+                    z: MAX_COORDINATE,
+                },
+            ];
+            assert_eq!(
+                Link::new(&far, 0, 1, Metric::Euclidean).measure,
+                3 * (MAX_COORDINATE as u128).pow(2)
+            );
+            let path = std::env::temp_dir().join("day08_far_points.txt");
+            std::fs::write(
+                &path,
+                format!("0,0,0\n{MAX_COORDINATE},0,{MAX_COORDINATE}\n"),
+            )
+            .unwrap();
+            assert_eq!(read_points(&path).unwrap().len(), 2);
+            std::fs::write(&path, format!("0,0,0\n1,{},2\n", usize::MAX)).unwrap();
+            assert!(read_points(&path).is_err());
+            std::fs::write(&path, format!("{0},{0},{0}\n", MAX_COORDINATE + 1)).unwrap();
+            assert!(read_points(&path).is_err());
+            std::fs::remove_file(&path).unwrap();
+        }
+
+        #[test]
+        fn streaming() {
+            let mut rng = Rng::new(42);
+            for round in 0..100 {
+                // Small spaces for lots of ties, large ones for spread out points
+                let size = [4, 100, 100_000][round % 3];
+                let count = 2 + rng.index(150);
+                let mut points = random_points(&mut rng, count, size);
+                if round % 5 == 4 {
+                    // One junction box far away from the rest
+                    points.push(Point {
+                        x: 1_000_000_000,
+                        y: 0,
+                        z: 0,
+                    });
+                }
+                let metric = [
+                    Metric::Euclidean,
+                    Metric::Manhattan,
+                    Metric::Chebyshev,
+                    Metric::SquaredEuclidean,
+                ][round % 4];
+                let all = exact_links(&points, metric);
+
+                let n = rng.index(all.len() + 10);
+                let shortest = links::shortest_links(&points, n, metric);
+                assert_eq!(shortest, all[..n.min(all.len())]);
+
+                let mut circuits = DisjointSet::new(points.len());
+                let kruskal: Vec<_> = all
+                    .into_iter()
+                    .filter(|link| circuits.union(link.from, link.to))
+                    .collect();
+                let tree = links::spanning_tree(&points, metric);
+                assert_eq!(tree, kruskal);
+            }
+
+            // Scales to lots of junction boxes
+            let points = random_points(&mut rng, 20_000, 1_000_000);
+            let metric = Metric::Euclidean;
+            assert_eq!(links::shortest_links(&points, 1_000, metric).len(), 1_000);
+            assert_eq!(links::spanning_tree(&points, metric).len(), 19_999);
+
+            // Clustered, with one far away: the first radius only depends on
+            // the nearest neighbors, not on the bounding box
+            let mut points = random_points(&mut rng, 20_000, 1_000);
+            points.push(Point {
+                x: 1_000_000_000,
+                y: 0,
+                z: 0,
+            });
+            assert!(links::first_radius(&points, 1_000, metric) < 10);
+            let shortest = links::shortest_links(&points, 1_000, metric);
+            assert_eq!(shortest.len(), 1_000);
+            assert!(shortest.windows(2).all(|pair| pair[0] < pair[1]));
+        }
+
+        #[test]
+        fn circuits() {
+            // Against naive relabelling of every element on each merge
+            let mut rng = Rng::new(8);
+            let mut circuits = DisjointSet::new(50);
+            let mut labels: Vec<usize> = (0..50).collect();
+            for _ in 0..100 {
+                let (first, second) = (rng.index(50), rng.index(50));
+                let (from, to) = (labels[first], labels[second]);
+                assert_eq!(circuits.union(first, second), from != to);
+                labels
+                    .iter_mut()
+                    .filter(|l| **l == from)
+                    .for_each(|l| *l = to);
+
+                let mut sizes: Vec<usize> = (0..50)
+                    .filter(|&l| labels.contains(&l))
+                    .map(|l| labels.iter().filter(|&&other| other == l).count())
+                    .collect();
+                sizes.sort_by(|a, b| b.cmp(a));
+                assert_eq!(circuits.components(), sizes.len());
+                assert_eq!(circuits.largest(3), sizes[..sizes.len().min(3)]);
+                assert_eq!(circuits.largest(100), sizes);
+                assert_eq!(
+                    circuits.size(first),
+                    labels.iter().filter(|&&l| l == to).count()
+                );
+            }
         }
 
         #[test]
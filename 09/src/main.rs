@@ -1,122 +1,57 @@
 #![allow(dead_code)]
 
+mod objective;
+mod polygon;
+mod raster;
+
 use itertools::Itertools;
 
-use std::io::Result;
+use objective::{Candidate, Objective, Outcome};
+use std::io::{Error, ErrorKind, Result};
 use std::path::Path;
-use utils::{Line, Point, Rectangle};
+use utils::{Point, Rectangle};
 
 fn read_points<P: AsRef<Path>>(path: P) -> Result<Vec<Point>> {
     let mut points: Vec<Point> = std::fs::read_to_string(path)?
         .lines()
-        .map(|line| {
-            let pt: Vec<usize> = line
-                .split(',')
-                .map(|num| num.parse::<usize>().unwrap())
-                .collect();
-            assert!(pt.len() == 2);
-            Point::new(pt[0], pt[1])
+        .enumerate()
+        .map(|(idx, line)| {
+            let invalid = || {
+                Error::new(
+                    ErrorKind::InvalidData,
+                    format!("line {}: expected 'x,y', got '{line}'", idx + 1),
+                )
+            };
+            let (x, y) = line.split_once(',').ok_or_else(invalid)?;
+            Ok(Point::new(
+                x.trim().parse().map_err(|_| invalid())?,
+                y.trim().parse().map_err(|_| invalid())?,
+            ))
         })
-        .collect();
+        .collect::<Result<_>>()?;
 
     // Add the first point to the back to be able to iterate over
     // the points and still end up with a closed polygon.
-    points.push(*points.first().unwrap());
+    if let Some(first) = points.first() {
+        points.push(*first);
+    }
 
+    polygon::validate(&points)?;
     Ok(points)
 }
 
-// Assumes one line is horizontal and the other is vertical
-fn lines_intersection_point(line1: &Line, line2: &Line) -> Option<Point> {
-    let (horizontal, vertical) = if line1.is_horizontal() {
-        (line1, line2)
+// Orthogonal polygons can be rasterized, anything else has to be checked
+// rectangle by rectangle.
+fn largest_inside(points: &[Point]) -> Option<Rectangle> {
+    if polygon::is_orthogonal(points) {
+        raster::largest_inside(points)
     } else {
-        (line2, line1)
-    };
-
-    (vertical.contains_y(horizontal.from.y) && horizontal.contains_x(vertical.from.x))
-        .then_some(Point::new(vertical.from.x, horizontal.from.y))
-}
-
-// Check if intersection should be ignored (it's at a rectangle edge endpoint or a polygon vertex)
-fn is_valid_intersection(rect_edge: &Line, outline_edge: &Line, points: &[Point]) -> bool {
-    lines_intersection_point(rect_edge, outline_edge)
-        .filter(|p| !rect_edge.is_endpoint(p))
-        .is_some_and(|p| !points.contains(&p))
-}
-
-// Assumes one set of lines is horizontal and the other is vertical
-fn lines_intersect(rect_edges: &[Line], outline_edges: &[Line], points: &[Point]) -> bool {
-    // PERFORMANCE(AE): Tried this with itertools::cartesian_product, and it was a lot slower :'(
-    for rect_edge in rect_edges {
-        for outline_edge in outline_edges {
-            if is_valid_intersection(rect_edge, outline_edge, points) {
-                return true;
-            }
-        }
-    }
-    false
-}
-
-// Ray casting algorithm to check if a point is inside a polygon
-fn point_in_polygon(point: &Point, points: &[Point]) -> bool {
-    let mut inside = false;
-
-    for (pt1, pt2) in points.iter().tuple_windows() {
-        // Check if point is exactly on a vertex
-        if point == pt1 || point == pt2 {
-            return true;
-        }
-
-        let (x1, y1) = (pt1.x as i64, pt1.y as i64);
-        let (x2, y2) = (pt2.x as i64, pt2.y as i64);
-        let (py, px) = (point.y as i64, point.x as i64);
-
-        if ((y1 > py) != (y2 > py)) && (px < (x2 - x1) * (py - y1) / (y2 - y1) + x1) {
-            inside = !inside;
-        }
+        polygon::largest_inside(points)
     }
-    inside
-}
-
-fn lines_from_points(points: &[Point]) -> (Vec<Line>, Vec<Line>) {
-    points
-        .iter()
-        .tuple_windows()
-        .map(|(from, to)| Line::new(*from, *to))
-        .fold((Vec::new(), Vec::new()), |mut lines, line| {
-            if line.is_horizontal() {
-                lines.0.push(line);
-            } else {
-                lines.1.push(line);
-            }
-            lines
-        })
 }
 
 fn larges_inside_rectangle(points: &[Point]) -> usize {
-    let (lines_horizontal, lines_vertical) = lines_from_points(points);
-
-    points
-        .iter()
-        .skip(1)
-        .combinations(2)
-        .map(|pair| Rectangle::from_points(pair[0], pair[1]))
-        .filter(|rect| !rect.is_degenerate())
-        // No line intersections with vertical or horizontal lines
-        .filter(|rect| {
-            !lines_intersect(&rect.horizontal_edges(), &lines_vertical, points)
-                && !lines_intersect(&rect.vertical_edges(), &lines_horizontal, points)
-        })
-        // All four corners inside the polygon
-        .filter(|rect| {
-            rect.corners()
-                .iter()
-                .all(|corner| point_in_polygon(corner, points))
-        })
-        .map(|rect| rect.area())
-        .max()
-        .unwrap()
+    largest_inside(points).unwrap().area()
 }
 
 fn largest_square(points: &[Point]) -> usize {
@@ -130,14 +65,49 @@ fn largest_square(points: &[Point]) -> usize {
         .unwrap()
 }
 
-fn main() {
-    println!("Run 'cargo test' instead ;)");
+fn main() -> Result<()> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.is_empty() {
+        println!("Run 'cargo test' instead ;)");
+        println!("Or: day_09 <file> [area|count|top:K|perimeter|square]");
+        return Ok(());
+    }
+
+    let objective: Objective = match args.get(1) {
+        Some(objective) => objective.parse()?,
+        None => Objective::Area,
+    };
+
+    let points = read_points(&args[0])?;
+    println!("Largest: {}", largest_square(&points));
+    match objective::select(&points, objective) {
+        Outcome::Count(count) => println!("Inside: {count}"),
+        Outcome::Rectangles(found) => {
+            for Candidate {
+                tiles: (p1, p2),
+                rect,
+            } in found
+            {
+                println!(
+                    "Inside: area {}, perimeter {} ({},{} - {},{})",
+                    rect.area(),
+                    rect.perimeter(),
+                    p1.x,
+                    p1.y,
+                    p2.x,
+                    p2.y
+                );
+            }
+        }
+    }
+    Ok(())
 }
 
 #[cfg(test)]
 mod tests {
     mod day09_movie_theater {
         use super::super::*;
+        use utils::Rng;
 
         #[test]
         fn sample() {
@@ -146,12 +116,457 @@ mod tests {
             assert_eq!(larges_inside_rectangle(&points), 24);
         }
 
+        #[test]
+        fn sample_raster() {
+            let points = read_points("data/sample.txt").unwrap();
+            let rect = raster::largest_inside(&points).unwrap();
+            assert_eq!(rect.area(), 24);
+            assert_eq!(rect, Rectangle::new(Point::new(2, 3), Point::new(9, 5)));
+
+            let raster = raster::Raster::new(&points);
+            // Corners inside, but the middle isn't
+            assert!(!raster.contains(&Rectangle::new(Point::new(2, 1), Point::new(11, 5))));
+            assert!(!raster.contains(&Rectangle::new(Point::new(7, 1), Point::new(11, 7))));
+            assert!(raster.contains(&Rectangle::new(Point::new(7, 1), Point::new(11, 5))));
+            assert!(raster.contains(&Rectangle::new(Point::new(9, 5), Point::new(11, 7))));
+            // Along the edge
+            assert!(raster.contains(&Rectangle::new(Point::new(2, 5), Point::new(9, 5))));
+        }
+
+        fn closed(corners: &[(usize, usize)]) -> Vec<Point> {
+            let mut points: Vec<Point> = corners.iter().map(|&(x, y)| Point::new(x, y)).collect();
+            points.push(points[0]);
+            points
+        }
+
+        #[test]
+        fn validation() {
+            let error = |corners: &[(usize, usize)]| {
+                polygon::validate(&closed(corners)).unwrap_err().to_string()
+            };
+            assert!(polygon::validate(&closed(&[(0, 0), (4, 0), (4, 4), (0, 4)])).is_ok());
+            // Collinear corners are fine
+            assert!(polygon::validate(&closed(&[(0, 0), (2, 0), (4, 0), (4, 4), (0, 4)])).is_ok());
+
+            // So are diagonal edges
+            assert!(polygon::validate(&closed(&[(0, 0), (4, 0), (4, 4), (1, 5)])).is_ok());
+            assert!(polygon::validate(&closed(&[(0, 0), (4, 0), (4, 4)])).is_ok());
+
+            assert_eq!(
+                error(&[(0, 0), (4, 0)]),
+                "a polygon needs at least 3 corners, got 2"
+            );
+            assert_eq!(
+                error(&[(0, 0), (4, 0), (0, 4), (4, 4)]),
+                "edges 2 and 4 intersect, the polygon isn't simple"
+            );
+            assert_eq!(
+                error(&[(0, 0), (4, 0), (4, 0), (4, 4), (0, 4)]),
+                "edge 2 has length zero"
+            );
+            // Turning back onto the previous edge
+            assert_eq!(
+                error(&[(0, 0), (4, 0), (2, 0), (2, 4), (0, 4)]),
+                "edges 1 and 2 intersect, the polygon isn't simple"
+            );
+            // Figure eight
+            assert_eq!(
+                error(&[(0, 0), (2, 0), (2, 4), (4, 4), (4, 2), (0, 2)]),
+                "edges 2 and 5 intersect, the polygon isn't simple"
+            );
+            // Two corners touching
+            assert_eq!(
+                error(&[
+                    (0, 0),
+                    (2, 0),
+                    (2, 2),
+                    (4, 2),
+                    (4, 4),
+                    (2, 4),
+                    (2, 2),
+                    (0, 2)
+                ]),
+                "edges 2 and 6 intersect, the polygon isn't simple"
+            );
+        }
+
+        #[test]
+        fn boundaries() {
+            let points = read_points("data/sample.txt").unwrap();
+            // Corners, edges, inside and just outside
+            assert!(polygon::contains(&Point::new(7, 1), &points));
+            assert!(polygon::contains(&Point::new(9, 1), &points));
+            assert!(polygon::contains(&Point::new(11, 4), &points));
+            assert!(polygon::contains(&Point::new(5, 5), &points));
+            assert!(polygon::contains(&Point::new(8, 4), &points));
+            assert!(!polygon::contains(&Point::new(6, 2), &points));
+            assert!(!polygon::contains(&Point::new(8, 6), &points));
+            assert!(!polygon::contains(&Point::new(12, 4), &points));
+        }
+
+        // Random orthogonal polygon: columns between increasing x coordinates,
+        // each spanning from a bottom to a top y, and overlapping their
+        // neighbors so the outline never touches itself. Sometimes mirrored
+        // along the diagonal, for polygons with notches left and right.
+        fn random_polygon(rng: &mut Rng) -> Vec<Point> {
+            let columns = 1 + rng.index(6);
+            let mut xs = vec![rng.index(3)];
+            let mut spans: Vec<(usize, usize)> = Vec::new();
+            while spans.len() < columns {
+                let bottom = rng.index(8);
+                let top = bottom + 1 + rng.index(8);
+                if spans
+                    .last()
+                    .is_none_or(|&(b, t)| b.max(bottom) < t.min(top))
+                {
+                    spans.push((bottom, top));
+                    xs.push(xs.last().unwrap() + 1 + rng.index(4));
+                }
+            }
+
+            let mut corners = vec![(xs[0], spans[0].0)];
+            for (idx, &(_, top)) in spans.iter().enumerate() {
+                corners.push((xs[idx], top));
+                corners.push((xs[idx + 1], top));
+            }
+            for (idx, &(bottom, _)) in spans.iter().enumerate().rev() {
+                corners.push((xs[idx + 1], bottom));
+                corners.push((xs[idx], bottom));
+            }
+            corners.dedup();
+            if corners.first() == corners.last() {
+                corners.pop();
+            }
+            if rng.bool() {
+                corners.iter_mut().for_each(|(x, y)| std::mem::swap(x, y));
+            }
+            closed(&corners)
+        }
+
+        // Every tile in the rectangle checked on its own
+        fn brute_force_contains(rect: &Rectangle, points: &[Point]) -> bool {
+            (rect.top_left.x..=rect.bottom_right.x)
+                .cartesian_product(rect.top_left.y..=rect.bottom_right.y)
+                .all(|(x, y)| polygon::contains(&Point::new(x, y), points))
+        }
+
+        #[test]
+        fn random_polygons() {
+            let mut rng = Rng::new(9);
+            for _ in 0..300 {
+                let points = random_polygon(&mut rng);
+                polygon::validate(&points).unwrap();
+
+                let raster = raster::Raster::new(&points);
+                let mut largest = 0;
+                for (p1, p2) in points[1..].iter().tuple_combinations() {
+                    let rect = Rectangle::from_points(p1, p2);
+                    let inside = brute_force_contains(&rect, &points);
+                    assert_eq!(raster.contains(&rect), inside, "{rect:?} in {points:?}");
+                    if inside {
+                        largest = largest.max(rect.area());
+                    }
+                }
+                assert_eq!(raster::largest_inside(&points).unwrap().area(), largest);
+            }
+        }
+
+        #[test]
+        fn diagonal_polygons() {
+            let triangle = closed(&[(0, 0), (8, 0), (0, 8)]);
+            assert_eq!(largest_inside(&triangle).unwrap().area(), 9);
+
+            // The corner (4,8) is on the diagonal edge
+            let pentagon = closed(&[(0, 0), (8, 0), (8, 4), (4, 8), (0, 8)]);
+            assert_eq!(larges_inside_rectangle(&pentagon), 45);
+            let region = polygon::Region::new(&pentagon);
+            assert!(region.contains(&Rectangle::new(Point::new(0, 0), Point::new(4, 8))));
+            assert!(!region.contains(&Rectangle::new(Point::new(0, 4), Point::new(8, 8))));
+            assert!(region.contains(&Rectangle::new(Point::new(0, 8), Point::new(4, 8))));
+
+            // A diagonal cutting a corner off, with all corners inside
+            let notched = closed(&[(0, 0), (4, 0), (6, 2), (8, 0), (8, 8), (0, 8)]);
+            let region = polygon::Region::new(&notched);
+            assert!(!region.contains(&Rectangle::new(Point::new(0, 0), Point::new(8, 8))));
+            assert!(!region.contains(&Rectangle::new(Point::new(4, 0), Point::new(8, 0))));
+            assert!(region.contains(&Rectangle::new(Point::new(0, 0), Point::new(4, 8))));
+            assert_eq!(larges_inside_rectangle(&notched), 7 * 7);
+
+            // A notch one tile wide has no tiles outside of the polygon, and
+            // a diagonal edge elsewhere doesn't change that
+            let slit = closed(&[
+                (0, 0),
+                (3, 0),
+                (3, 5),
+                (4, 5),
+                (4, 0),
+                (8, 0),
+                (8, 8),
+                (0, 8),
+            ]);
+            assert_eq!(raster::largest_inside(&slit).unwrap().area(), 81);
+            assert_eq!(polygon::largest_inside(&slit).unwrap().area(), 81);
+            let cut = closed(&[
+                (0, 0),
+                (3, 0),
+                (3, 5),
+                (4, 5),
+                (4, 0),
+                (8, 0),
+                (8, 8),
+                (1, 8),
+                (0, 7),
+            ]);
+            assert_eq!(larges_inside_rectangle(&cut), 72);
+            let region = polygon::Region::new(&cut);
+            assert!(region.contains(&Rectangle::new(Point::new(1, 0), Point::new(8, 8))));
+            assert!(!region.contains(&Rectangle::new(Point::new(0, 0), Point::new(8, 8))));
+        }
+
+        #[test]
+        fn random_diagonal_polygons() {
+            // Random orthogonal polygons with a corner cut off, against every
+            // tile checked on its own
+            let mut rng = Rng::new(480);
+            let mut checked = 0;
+            while checked < 200 {
+                let mut points = random_polygon(&mut rng);
+                points.pop();
+                let corner = rng.index(points.len());
+                let (prev, next) = (
+                    points[(corner + points.len() - 1) % points.len()],
+                    points[(corner + 1) % points.len()],
+                );
+                let at = points[corner];
+                let toward = |p: Point| {
+                    Point::new(
+                        if p.x < at.x {
+                            at.x - 1
+                        } else if p.x > at.x {
+                            at.x + 1
+                        } else {
+                            at.x
+                        },
+                        if p.y < at.y {
+                            at.y - 1
+                        } else if p.y > at.y {
+                            at.y + 1
+                        } else {
+                            at.y
+                        },
+                    )
+                };
+                points.splice(corner..=corner, [toward(prev), toward(next)]);
+                let points = closed(&points.iter().map(|p| (p.x, p.y)).collect::<Vec<_>>());
+                if polygon::validate(&points).is_err() || polygon::is_orthogonal(&points) {
+                    continue;
+                }
+                checked += 1;
+
+                let region = polygon::Region::new(&points);
+                let mut largest = None;
+                for (p1, p2) in points[1..].iter().tuple_combinations() {
+                    let rect = Rectangle::from_points(p1, p2);
+                    let inside = brute_force_contains(&rect, &points);
+                    assert_eq!(region.contains(&rect), inside, "{rect:?} in {points:?}");
+                    if inside {
+                        largest = largest.max(Some(rect.area()));
+                    }
+                }
+                assert_eq!(
+                    polygon::largest_inside(&points).map(|rect| rect.area()),
+                    largest
+                );
+            }
+        }
+
+        // Convex polygon around random points (monotone chain), without
+        // collinear corners
+        fn random_convex(rng: &mut Rng) -> Vec<Point> {
+            let mut points: Vec<Point> = (0..3 + rng.index(10))
+                .map(|_| Point::new(rng.index(20), rng.index(20)))
+                .sorted_by_key(|p| (p.x, p.y))
+                .dedup()
+                .collect();
+            let cross = |o: &Point, a: &Point, b: &Point| {
+                (a.x as i64 - o.x as i64) * (b.y as i64 - o.y as i64)
+                    - (a.y as i64 - o.y as i64) * (b.x as i64 - o.x as i64)
+            };
+            let mut hull: Vec<Point> = Vec::new();
+            for pass in 0..2 {
+                let start = hull.len();
+                for point in &points {
+                    while hull.len() >= start + 2
+                        && cross(&hull[hull.len() - 2], &hull[hull.len() - 1], point) <= 0
+                    {
+                        hull.pop();
+                    }
+                    hull.push(*point);
+                }
+                hull.pop();
+                if pass == 0 {
+                    points.reverse();
+                }
+            }
+            hull.push(hull[0]);
+            hull
+        }
+
+        #[test]
+        fn random_general_polygons() {
+            let mut rng = Rng::new(48);
+
+            // Inside a convex polygon, a rectangle is inside if its corners are
+            let mut convex = 0;
+            while convex < 200 {
+                let points = random_convex(&mut rng);
+                if polygon::validate(&points).is_err() {
+                    continue;
+                }
+                convex += 1;
+                let region = polygon::Region::new(&points);
+                for (p1, p2) in points[1..].iter().tuple_combinations() {
+                    let rect = Rectangle::from_points(p1, p2);
+                    let corners = rect.corners().iter().all(|c| polygon::contains(c, &points));
+                    assert_eq!(region.contains(&rect), corners, "{rect:?} in {points:?}");
+                }
+            }
+
+            // Both count tiles, so they agree on orthogonal polygons, even
+            // where an edge is right next to the one across
+            for _ in 0..200 {
+                let points = random_polygon(&mut rng);
+                let (raster, region) =
+                    (raster::Raster::new(&points), polygon::Region::new(&points));
+                for (p1, p2) in points[1..].iter().tuple_combinations() {
+                    let rect = Rectangle::from_points(p1, p2);
+                    assert_eq!(
+                        region.contains(&rect),
+                        raster.contains(&rect),
+                        "{rect:?} in {points:?}"
+                    );
+                }
+                assert_eq!(
+                    polygon::largest_inside(&points).unwrap().area(),
+                    raster::largest_inside(&points).unwrap().area()
+                );
+            }
+        }
+
+        #[test]
+        fn objectives() {
+            let points = read_points("data/sample.txt").unwrap();
+            let rects =
+                |objective: &str| match objective::select(&points, objective.parse().unwrap()) {
+                    Outcome::Rectangles(found) => {
+                        // Both corners are always red tiles
+                        assert!(found.iter().all(|candidate| {
+                            let (p1, p2) = candidate.tiles;
+                            points.contains(&p1)
+                                && points.contains(&p2)
+                                && Rectangle::from_points(&p1, &p2) == candidate.rect
+                        }));
+                        found
+                            .iter()
+                            .map(|candidate| candidate.rect)
+                            .collect::<Vec<_>>()
+                    }
+                    Outcome::Count(_) => unreachable!(),
+                };
+
+            assert_eq!(
+                rects("area"),
+                [Rectangle::new(Point::new(2, 3), Point::new(9, 5))]
+            );
+            let top = rects("top:3");
+            assert_eq!(
+                top.iter().map(|rect| rect.area()).collect::<Vec<_>>(),
+                [24, 21, 18]
+            );
+            assert_eq!(rects("top:100").len(), 16);
+            assert_eq!(rects("perimeter")[0].perimeter(), 2 * (8 + 3));
+            assert_eq!(rects("square")[0].area(), 9);
+            assert_eq!(
+                objective::select(&points, Objective::Count),
+                Outcome::Count(16)
+            );
+
+            // Red tiles in all four corners still make one rectangle
+            let square = closed(&[(0, 0), (5, 0), (5, 5), (0, 5)]);
+            assert_eq!(
+                objective::select(&square, Objective::Count),
+                Outcome::Count(5)
+            );
+            let Outcome::Rectangles(top) = objective::select(&square, Objective::TopK(2)) else {
+                unreachable!()
+            };
+            assert_eq!(top.len(), 2);
+            assert_eq!(
+                top[0].rect,
+                Rectangle::new(Point::new(0, 0), Point::new(5, 5))
+            );
+            assert_eq!(top[1].rect.area(), 6);
+
+            assert!("top:".parse::<Objective>().is_err());
+            assert!("volume".parse::<Objective>().is_err());
+        }
+
+        #[test]
+        fn random_objectives() {
+            // Against the brute force on all rectangles between corners
+            let mut rng = Rng::new(49);
+            for _ in 0..200 {
+                let points = random_polygon(&mut rng);
+                let mut inside: Vec<Rectangle> = Vec::new();
+                for (p1, p2) in points[1..].iter().tuple_combinations() {
+                    let rect = Rectangle::from_points(p1, p2);
+                    if brute_force_contains(&rect, &points) && !inside.contains(&rect) {
+                        inside.push(rect);
+                    }
+                }
+
+                assert_eq!(
+                    objective::select(&points, Objective::Count),
+                    Outcome::Count(inside.len())
+                );
+                let best = |objective| match objective::select(&points, objective) {
+                    Outcome::Rectangles(found) => found
+                        .iter()
+                        .map(|candidate| candidate.rect)
+                        .collect::<Vec<_>>(),
+                    Outcome::Count(_) => unreachable!(),
+                };
+                assert_eq!(
+                    best(Objective::Perimeter)[0].perimeter(),
+                    inside.iter().map(|rect| rect.perimeter()).max().unwrap()
+                );
+                assert_eq!(
+                    best(Objective::Square).first().map(|rect| rect.area()),
+                    inside
+                        .iter()
+                        .filter(|rect| rect.is_square())
+                        .map(|rect| rect.area())
+                        .max()
+                );
+
+                let k = rng.index(5);
+                inside.sort_by_key(|rect| std::cmp::Reverse(rect.area()));
+                let areas =
+                    |rects: &[Rectangle]| rects.iter().map(|rect| rect.area()).collect::<Vec<_>>();
+                assert_eq!(
+                    areas(&best(Objective::TopK(k))),
+                    areas(&inside[..k.min(inside.len())])
+                );
+            }
+        }
+
         #[test]
         #[cfg(feature = "private")]
         fn input() {
             let points = read_points("data/input.txt").unwrap();
             assert_eq!(largest_square(&points), 4740155680);
             assert_eq!(larges_inside_rectangle(&points), 1543501936);
+            assert_eq!(raster::largest_inside(&points).unwrap().area(), 1543501936);
         }
     }
 }
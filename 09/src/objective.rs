@@ -0,0 +1,131 @@
+use crate::polygon::{self, Region};
+use crate::raster::Raster;
+use itertools::Itertools;
+use std::io::{Error, ErrorKind};
+use std::str::FromStr;
+use utils::{Point, Rectangle};
+
+/// What to look for among the rectangles inside the polygon
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum Objective {
+    /// Largest by area
+    Area,
+    /// Number of rectangles
+    Count,
+    /// The k largest by area, largest first
+    TopK(usize),
+    /// Largest by perimeter
+    Perimeter,
+    /// Largest with equal sides
+    Square,
+}
+
+/// Rectangle inside the polygon, and the two red tiles in its corners
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct Candidate {
+    pub tiles: (Point, Point),
+    pub rect: Rectangle,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Outcome {
+    Count(usize),
+    /// Best first; empty if no rectangle fits
+    Rectangles(Vec<Candidate>),
+}
+
+/// Checks rectangles against the polygon, the fastest way it allows
+pub enum Inside {
+    Raster(Raster),
+    Region(Region),
+}
+
+impl Inside {
+    pub fn new(points: &[Point]) -> Self {
+        if polygon::is_orthogonal(points) {
+            Inside::Raster(Raster::new(points))
+        } else {
+            Inside::Region(Region::new(points))
+        }
+    }
+
+    pub fn contains(&self, rect: &Rectangle) -> bool {
+        match self {
+            Inside::Raster(raster) => raster.contains(rect),
+            Inside::Region(region) => region.contains(rect),
+        }
+    }
+}
+
+/// All rectangles with red tiles in two opposite corners that are inside the
+/// closed polygon (the first point repeated at the end). Every rectangle
+/// comes up once, even with red tiles in all four corners.
+pub fn candidates<'a>(
+    points: &'a [Point],
+    inside: &'a Inside,
+) -> impl Iterator<Item = Candidate> + 'a {
+    points[1..]
+        .iter()
+        .tuple_combinations()
+        .map(|(p1, p2)| Candidate {
+            tiles: (*p1, *p2),
+            rect: Rectangle::from_points(p1, p2),
+        })
+        .unique_by(|candidate| {
+            let Rectangle {
+                top_left,
+                bottom_right,
+            } = candidate.rect;
+            (top_left.x, top_left.y, bottom_right.x, bottom_right.y)
+        })
+        .filter(|candidate| inside.contains(&candidate.rect))
+}
+
+pub fn select(points: &[Point], objective: Objective) -> Outcome {
+    let inside = Inside::new(points);
+    let found = candidates(points, &inside);
+    match objective {
+        Objective::Count => Outcome::Count(found.count()),
+        Objective::Area => Outcome::Rectangles(
+            found
+                .max_by_key(|candidate| candidate.rect.area())
+                .into_iter()
+                .collect(),
+        ),
+        Objective::TopK(k) => Outcome::Rectangles(
+            found
+                .k_largest_by_key(k, |candidate| candidate.rect.area())
+                .collect(),
+        ),
+        Objective::Perimeter => Outcome::Rectangles(
+            found
+                .max_by_key(|candidate| candidate.rect.perimeter())
+                .into_iter()
+                .collect(),
+        ),
+        Objective::Square => Outcome::Rectangles(
+            found
+                .filter(|candidate| candidate.rect.is_square())
+                .max_by_key(|candidate| candidate.rect.area())
+                .into_iter()
+                .collect(),
+        ),
+    }
+}
+
+impl FromStr for Objective {
+    type Err = Error;
+
+    /// Parses "area", "count", "top:K", "perimeter" or "square"
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || Error::new(ErrorKind::InvalidInput, format!("invalid objective '{s}'"));
+        match s.split_once(':') {
+            None if s == "area" => Ok(Objective::Area),
+            None if s == "count" => Ok(Objective::Count),
+            None if s == "perimeter" => Ok(Objective::Perimeter),
+            None if s == "square" => Ok(Objective::Square),
+            Some(("top", k)) => Ok(Objective::TopK(k.parse().map_err(|_| invalid())?)),
+            _ => Err(invalid()),
+        }
+    }
+}
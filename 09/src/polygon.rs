@@ -0,0 +1,263 @@
+use itertools::Itertools;
+use std::cmp::Ordering;
+use std::io::{Error, ErrorKind, Result};
+use utils::{Point, Rectangle};
+
+// Sign of the cross product of (b - a) and (c - a): positive if c is left of
+// the line from a to b (counterclockwise), 0 if the three are collinear.
+fn orientation(a: &Point, b: &Point, c: &Point) -> i128 {
+    let (ax, ay) = (a.x as i128, a.y as i128);
+    let (bx, by) = (b.x as i128, b.y as i128);
+    let (cx, cy) = (c.x as i128, c.y as i128);
+    ((bx - ax) * (cy - ay) - (by - ay) * (cx - ax)).signum()
+}
+
+// Only for collinear points: is p within the bounding box of the segment?
+fn within(p: &Point, from: &Point, to: &Point) -> bool {
+    from.x.min(to.x) <= p.x
+        && p.x <= from.x.max(to.x)
+        && from.y.min(to.y) <= p.y
+        && p.y <= from.y.max(to.y)
+}
+
+/// True if the point is on the segment, including its ends
+pub fn on_segment(p: &Point, from: &Point, to: &Point) -> bool {
+    orientation(from, to, p) == 0 && within(p, from, to)
+}
+
+/// True if the two segments have at least one point in common
+pub fn segments_touch(a: (&Point, &Point), b: (&Point, &Point)) -> bool {
+    let (o1, o2) = (orientation(a.0, a.1, b.0), orientation(a.0, a.1, b.1));
+    let (o3, o4) = (orientation(b.0, b.1, a.0), orientation(b.0, b.1, a.1));
+    (o1 * o2 < 0 && o3 * o4 < 0)
+        || on_segment(b.0, a.0, a.1)
+        || on_segment(b.1, a.0, a.1)
+        || on_segment(a.0, b.0, b.1)
+        || on_segment(a.1, b.0, b.1)
+}
+
+/// True if the point is inside the closed polygon (the first point repeated
+/// at the end) or on one of its edges. Exact, as it only uses integers.
+pub fn contains(point: &Point, points: &[Point]) -> bool {
+    let mut inside = false;
+    for (from, to) in points.iter().tuple_windows() {
+        if on_segment(point, from, to) {
+            return true;
+        }
+        // Does a ray to the right cross the edge? Edges include their lower
+        // end but not their upper one, so vertices don't count twice.
+        if (from.y > point.y) != (to.y > point.y) {
+            let (lower, upper) = if from.y < to.y {
+                (from, to)
+            } else {
+                (to, from)
+            };
+            if orientation(lower, upper, point) > 0 {
+                inside = !inside;
+            }
+        }
+    }
+    inside
+}
+
+fn invalid(what: String) -> Error {
+    Error::new(ErrorKind::InvalidData, what)
+}
+
+/// True if all edges are horizontal or vertical
+pub fn is_orthogonal(points: &[Point]) -> bool {
+    points
+        .iter()
+        .tuple_windows()
+        .all(|(from, to)| from.x == to.x || from.y == to.y)
+}
+
+/// Checks that the points form a closed (the first point repeated at the
+/// end), simple polygon: at least three corners, no edge of length zero and
+/// no edges touching each other, except for neighbors at their shared corner.
+pub fn validate(points: &[Point]) -> Result<()> {
+    if points.len() < 4 {
+        return Err(invalid(format!(
+            "a polygon needs at least 3 corners, got {}",
+            points.len().saturating_sub(1)
+        )));
+    }
+    if points.first() != points.last() {
+        return Err(invalid("polygon isn't closed".into()));
+    }
+
+    let edges: Vec<(&Point, &Point)> = points.iter().tuple_windows().collect();
+    if let Some(idx) = edges.iter().position(|(from, to)| from == to) {
+        return Err(invalid(format!("edge {} has length zero", idx + 1)));
+    }
+
+    for (i, j) in (0..edges.len()).tuple_combinations() {
+        let (first, second) = (edges[i], edges[j]);
+        let adjacent = j == i + 1 || (i == 0 && j == edges.len() - 1);
+        let overlapping = if adjacent {
+            // Neighbors share a corner; they only overlap if one turns back
+            // onto the other.
+            let (a, b, c) = if j == i + 1 {
+                (first.0, first.1, second.1)
+            } else {
+                (second.0, second.1, first.1)
+            };
+            on_segment(c, a, b) || on_segment(a, b, c)
+        } else {
+            segments_touch(first, second)
+        };
+        if overlapping {
+            return Err(invalid(format!(
+                "edges {} and {} intersect, the polygon isn't simple",
+                i + 1,
+                j + 1
+            )));
+        }
+    }
+    Ok(())
+}
+
+// Exact fraction with a positive denominator
+#[derive(Debug, Copy, Clone)]
+struct Fraction {
+    num: i128,
+    den: i128,
+}
+
+impl Fraction {
+    fn new(num: i128, den: i128) -> Self {
+        if den < 0 {
+            Fraction {
+                num: -num,
+                den: -den,
+            }
+        } else {
+            Fraction { num, den }
+        }
+    }
+
+    fn cmp(&self, other: &Fraction) -> Ordering {
+        (self.num * other.den).cmp(&(other.num * self.den))
+    }
+
+    fn floor(&self) -> i128 {
+        self.num.div_euclid(self.den)
+    }
+
+    fn ceil(&self) -> i128 {
+        -(-self.num).div_euclid(self.den)
+    }
+}
+
+// Tiles of row y inside the polygon or on its outline, as sorted ranges of x
+// (inclusive) that don't touch each other
+fn row_tiles(points: &[Point], y: usize) -> Vec<(usize, usize)> {
+    let y = y as i128;
+    let mut crossings = Vec::new();
+    let mut ranges: Vec<(i128, i128)> = Vec::new();
+
+    for (from, to) in points.iter().tuple_windows() {
+        let (x1, y1, x2, y2) = (from.x as i128, from.y as i128, to.x as i128, to.y as i128);
+        if y < y1.min(y2) || y > y1.max(y2) {
+            continue;
+        }
+        if y1 == y2 {
+            ranges.push((x1.min(x2), x1.max(x2)));
+            continue;
+        }
+        let x = Fraction::new(x1 * (y2 - y1) + (y - y1) * (x2 - x1), y2 - y1);
+        if x.num % x.den == 0 {
+            ranges.push((x.num / x.den, x.num / x.den));
+        }
+        // Same rule as in contains(), so every crossing counts once
+        if (y1 > y) != (y2 > y) {
+            crossings.push(x);
+        }
+    }
+
+    // Inside between every other pair of crossings, not counting the
+    // crossings themselves; those are on the outline, and in ranges already
+    crossings.sort_unstable_by(Fraction::cmp);
+    for pair in crossings.chunks(2) {
+        let (low, high) = (pair[0].floor() + 1, pair[1].ceil() - 1);
+        if low <= high {
+            ranges.push((low, high));
+        }
+    }
+
+    ranges.sort_unstable();
+    let mut tiles: Vec<(usize, usize)> = Vec::new();
+    for (low, high) in ranges {
+        match tiles.last_mut() {
+            Some(last) if low <= last.1 as i128 + 1 => last.1 = last.1.max(high as usize),
+            _ => tiles.push((low as usize, high as usize)),
+        }
+    }
+    tiles
+}
+
+/// Any simple polygon, for checking which rectangles it contains. Like on
+/// the raster, a rectangle is inside if all of its tiles are inside or on the
+/// outline; with diagonal edges, that's not the same as all of its area.
+pub struct Region {
+    // Runs of rows with the same tiles inside: the first row of every run,
+    // and those tiles (see row_tiles)
+    rows: Vec<(usize, Vec<(usize, usize)>)>,
+    // One past the last row of the polygon
+    end: usize,
+}
+
+impl Region {
+    /// O(n) for every row the polygon spans
+    pub fn new(points: &[Point]) -> Self {
+        let (top, bottom) = points
+            .iter()
+            .map(|point| point.y)
+            .minmax()
+            .into_option()
+            .unwrap_or((0, 0));
+        let mut rows: Vec<(usize, Vec<(usize, usize)>)> = Vec::new();
+        for y in top..=bottom {
+            let tiles = row_tiles(points, y);
+            if rows.last().is_none_or(|(_, last)| *last != tiles) {
+                rows.push((y, tiles));
+            }
+        }
+        Region {
+            rows,
+            end: bottom + 1,
+        }
+    }
+
+    /// True if every tile of the rectangle is inside the polygon (or on its
+    /// outline)
+    pub fn contains(&self, rect: &Rectangle) -> bool {
+        let (left, right) = (rect.top_left.x, rect.bottom_right.x);
+        let (top, bottom) = (rect.top_left.y, rect.bottom_right.y);
+        if self.rows.first().is_none_or(|(first, _)| top < *first) || bottom >= self.end {
+            return false;
+        }
+
+        let run = self.rows.partition_point(|(start, _)| *start <= top) - 1;
+        self.rows[run..]
+            .iter()
+            .take_while(|(start, _)| *start <= bottom)
+            .all(|(_, tiles)| {
+                let idx = tiles.partition_point(|(low, _)| *low <= left);
+                idx > 0 && tiles[idx - 1].1 >= right
+            })
+    }
+}
+
+/// Largest rectangle between two corners inside any simple polygon. Checking
+/// one takes a step for every run of rows it spans, but the candidates are
+/// checked from large to small.
+pub fn largest_inside(points: &[Point]) -> Option<Rectangle> {
+    let region = Region::new(points);
+    points[1..]
+        .iter()
+        .tuple_combinations()
+        .map(|(p1, p2)| Rectangle::from_points(p1, p2))
+        .sorted_unstable_by_key(|rect| std::cmp::Reverse(rect.area()))
+        .find(|rect| region.contains(rect))
+}
@@ -0,0 +1,133 @@
+use itertools::Itertools;
+use utils::{Grid, Point, Rectangle};
+
+/// The tiles of an orthogonal polygon, on a grid compressed to the polygon's
+/// coordinates: every distinct x (and y) gets a column (row), and so does
+/// every gap between two of them. Everything inside a gap looks the same, so
+/// one cell stands in for all of its tiles.
+pub struct Raster {
+    xs: Vec<usize>,
+    ys: Vec<usize>,
+    // Summed-area table of the cells outside of the polygon: the number of
+    // them above and left of every cell, so one larger in both directions.
+    outside: Grid<usize>,
+}
+
+fn compress(values: impl Iterator<Item = usize>) -> Vec<usize> {
+    values.sorted_unstable().dedup().collect()
+}
+
+// Compressed index of a coordinate of the polygon
+fn index(coordinates: &[usize], value: usize) -> usize {
+    2 * coordinates.binary_search(&value).unwrap()
+}
+
+// A tile for the compressed index, None for a gap without any tiles
+fn representative(coordinates: &[usize], idx: usize) -> Option<usize> {
+    if idx.is_multiple_of(2) {
+        return Some(coordinates[idx / 2]);
+    }
+    let next = coordinates[idx / 2] + 1;
+    (next < coordinates[idx / 2 + 1]).then_some(next)
+}
+
+impl Raster {
+    /// Rasterizes the closed polygon (the first point repeated at the end)
+    pub fn new(points: &[Point]) -> Self {
+        let xs = compress(points.iter().map(|point| point.x));
+        let ys = compress(points.iter().map(|point| point.y));
+        let (width, height) = (2 * xs.len() - 1, 2 * ys.len() - 1);
+        let mut inside = Grid::new(width, height, false);
+
+        for (from, to) in points.iter().tuple_windows() {
+            let (x1, x2) = (index(&xs, from.x), index(&xs, to.x));
+            let (y1, y2) = (index(&ys, from.y), index(&ys, to.y));
+            for x in x1.min(x2)..=x1.max(x2) {
+                for y in y1.min(y2)..=y1.max(y2) {
+                    inside.set(x, y, true);
+                }
+            }
+        }
+
+        // Everything else is inside if there's an odd number of vertical
+        // edges to the left. Edges count from their top row up to, but not
+        // including, their bottom row, so each crossing counts exactly once.
+        let verticals: Vec<(usize, usize, usize)> = points
+            .iter()
+            .tuple_windows()
+            .filter(|(from, to)| from.x == to.x)
+            .map(|(from, to)| (from.x, from.y.min(to.y), from.y.max(to.y)))
+            .collect();
+        for cy in 0..height {
+            let Some(y) = representative(&ys, cy) else {
+                (0..width).for_each(|cx| {
+                    inside.set(cx, cy, true);
+                });
+                continue;
+            };
+            let crossings: Vec<usize> = verticals
+                .iter()
+                .filter(|(_, top, bottom)| (*top..*bottom).contains(&y))
+                .map(|(x, _, _)| *x)
+                .sorted_unstable()
+                .collect();
+
+            let mut crossed = 0;
+            for cx in 0..width {
+                match representative(&xs, cx) {
+                    Some(x) => {
+                        while crossed < crossings.len() && crossings[crossed] < x {
+                            crossed += 1;
+                        }
+                        if crossed % 2 == 1 {
+                            inside.set(cx, cy, true);
+                        }
+                    }
+                    None => {
+                        inside.set(cx, cy, true);
+                    }
+                }
+            }
+        }
+
+        let mut outside = Grid::new(width + 1, height + 1, 0);
+        for y in 0..height {
+            for x in 0..width {
+                let sum = outside.get(x, y + 1).unwrap() + outside.get(x + 1, y).unwrap()
+                    - outside.get(x, y).unwrap()
+                    + usize::from(!inside.get(x, y).unwrap());
+                outside.set(x + 1, y + 1, sum);
+            }
+        }
+
+        Raster { xs, ys, outside }
+    }
+
+    /// True if every tile of the rectangle is inside the polygon (or on its
+    /// edge). The corners need to be on the polygon's coordinates, which they
+    /// are for rectangles between two red tiles.
+    pub fn contains(&self, rect: &Rectangle) -> bool {
+        let (x1, y1) = (
+            index(&self.xs, rect.top_left.x),
+            index(&self.ys, rect.top_left.y),
+        );
+        let (x2, y2) = (
+            index(&self.xs, rect.bottom_right.x) + 1,
+            index(&self.ys, rect.bottom_right.y) + 1,
+        );
+        let sum = |x, y| self.outside.get(x, y).unwrap();
+        sum(x2, y2) + sum(x1, y1) - sum(x1, y2) - sum(x2, y1) == 0
+    }
+}
+
+/// Largest rectangle with red tiles in two opposite corners, and only red or
+/// green tiles inside. O(n²) after the O(n²) rasterization.
+pub fn largest_inside(points: &[Point]) -> Option<Rectangle> {
+    let raster = Raster::new(points);
+    points[1..]
+        .iter()
+        .tuple_combinations()
+        .map(|(p1, p2)| Rectangle::from_points(p1, p2))
+        .filter(|rect| raster.contains(rect))
+        .max_by_key(|rect| rect.area())
+}
@@ -0,0 +1,203 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+
+/// Most free variables to try all combinations of, that's 2^24 of them;
+/// beyond that, the light states are searched instead
+pub const MAX_FREE: usize = 24;
+
+/// Fixed number of bits, stored in 64 bit words
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
+pub struct BitSet {
+    words: Vec<u64>,
+    len: usize,
+}
+
+impl BitSet {
+    /// All bits cleared
+    pub fn new(len: usize) -> Self {
+        BitSet {
+            words: vec![0; len.div_ceil(64)],
+            len,
+        }
+    }
+
+    pub fn from_indices(len: usize, indices: impl IntoIterator<Item = usize>) -> Self {
+        let mut bits = BitSet::new(len);
+        indices.into_iter().for_each(|idx| bits.set(idx, true));
+        bits
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn get(&self, idx: usize) -> bool {
+        assert!(idx < self.len);
+        self.words[idx / 64] & (1 << (idx % 64)) != 0
+    }
+
+    pub fn set(&mut self, idx: usize, value: bool) {
+        assert!(idx < self.len);
+        if value {
+            self.words[idx / 64] |= 1 << (idx % 64);
+        } else {
+            self.words[idx / 64] &= !(1 << (idx % 64));
+        }
+    }
+
+    /// Flips every bit that's set in `other`, which needs the same length
+    pub fn xor(&mut self, other: &BitSet) {
+        assert_eq!(self.len, other.len);
+        self.words
+            .iter_mut()
+            .zip(&other.words)
+            .for_each(|(word, other)| *word ^= other);
+    }
+
+    pub fn count_ones(&self) -> usize {
+        self.words
+            .iter()
+            .map(|word| word.count_ones() as usize)
+            .sum()
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.words.iter().all(|&word| word == 0)
+    }
+
+    /// Indices of all set bits, in increasing order
+    pub fn ones(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..self.len).filter(|&idx| self.get(idx))
+    }
+}
+
+/// Buttons (by index) to press to get from all lights off to the target,
+/// using as few buttons as possible; None if the target can't be reached.
+///
+/// Pressing a button twice undoes it, so this is a linear system over GF(2)
+/// with a variable per button and an equation per light. Gaussian elimination
+/// gives one solution and a basis of the null space, with a vector for every
+/// free variable; all 2^free combinations of those are tried for the one with
+/// the fewest presses. With more than MAX_FREE of them, a breadth first
+/// search over the light states takes over: there are only 2^rank of those,
+/// and with that many free variables the rank is small next to them.
+pub fn solve(buttons: &[BitSet], target: &BitSet) -> Option<Vec<usize>> {
+    // Buttons that toggle nothing, or the same lights as an earlier one, are
+    // never part of the fewest presses; leaving them out keeps them from
+    // adding free variables.
+    let mut seen = HashSet::new();
+    let useful: Vec<usize> = (0..buttons.len())
+        .filter(|&idx| !buttons[idx].is_zero() && seen.insert(&buttons[idx]))
+        .collect();
+    let buttons: Vec<&BitSet> = useful.iter().map(|&idx| &buttons[idx]).collect();
+
+    // One row per light: which buttons toggle it, and whether it has to be on
+    let mut rows: Vec<(BitSet, bool)> = (0..target.len())
+        .map(|light| {
+            let toggles = buttons.iter().map(|button| button.get(light));
+            let row = BitSet::from_indices(
+                buttons.len(),
+                toggles
+                    .enumerate()
+                    .filter(|(_, on)| *on)
+                    .map(|(idx, _)| idx),
+            );
+            (row, target.get(light))
+        })
+        .collect();
+
+    // Reduced row echelon form; pivots[r] is the button of row r
+    let mut pivots: Vec<usize> = Vec::new();
+    for button in 0..buttons.len() {
+        let rank = pivots.len();
+        let Some(found) = (rank..rows.len()).find(|&row| rows[row].0.get(button)) else {
+            continue;
+        };
+        rows.swap(rank, found);
+        let (pivot, value) = rows[rank].clone();
+        for (row, (bits, rhs)) in rows.iter_mut().enumerate() {
+            if row != rank && bits.get(button) {
+                bits.xor(&pivot);
+                *rhs ^= value;
+            }
+        }
+        pivots.push(button);
+    }
+
+    // Rows without a pivot read 0 = rhs
+    if rows[pivots.len()..].iter().any(|(_, rhs)| *rhs) {
+        return None;
+    }
+
+    // Particular solution with all free variables 0, and one null space
+    // vector for every free variable set to 1
+    let mut solution = BitSet::new(buttons.len());
+    for (row, &button) in pivots.iter().enumerate() {
+        solution.set(button, rows[row].1);
+    }
+    let free: Vec<usize> = (0..buttons.len())
+        .filter(|button| !pivots.contains(button))
+        .collect();
+    if free.len() > MAX_FREE {
+        let mut presses: Vec<usize> = search(&buttons, target)?
+            .into_iter()
+            .map(|idx| useful[idx])
+            .collect();
+        presses.sort_unstable();
+        return Some(presses);
+    }
+    let basis: Vec<BitSet> = free
+        .iter()
+        .map(|&free| {
+            let mut vector = BitSet::from_indices(buttons.len(), [free]);
+            for (row, &button) in pivots.iter().enumerate() {
+                vector.set(button, rows[row].0.get(free));
+            }
+            vector
+        })
+        .collect();
+
+    // Gray code order, so every step flips just one basis vector
+    let mut best = solution.clone();
+    for step in 1..1u64 << basis.len() {
+        solution.xor(&basis[step.trailing_zeros() as usize]);
+        if solution.count_ones() < best.count_ones() {
+            best = solution.clone();
+        }
+    }
+    Some(best.ones().map(|idx| useful[idx]).collect())
+}
+
+// Fewest buttons to press by breadth first search over the light states,
+// for a target that's known to be reachable
+fn search(buttons: &[&BitSet], target: &BitSet) -> Option<Vec<usize>> {
+    // State -> the state before it and the button pressed to get here
+    let start = BitSet::new(target.len());
+    let mut previous: HashMap<BitSet, Option<(BitSet, usize)>> = HashMap::new();
+    previous.insert(start.clone(), None);
+    let mut queue = VecDeque::from([start]);
+
+    while let Some(state) = queue.pop_front() {
+        if state == *target {
+            let mut presses = Vec::new();
+            let mut current = state;
+            while let Some((before, button)) = previous[&current].clone() {
+                presses.push(button);
+                current = before;
+            }
+            return Some(presses);
+        }
+        for (idx, button) in buttons.iter().enumerate() {
+            let mut next = state.clone();
+            next.xor(button);
+            if !previous.contains_key(&next) {
+                previous.insert(next.clone(), Some((state.clone(), idx)));
+                queue.push_back(next);
+            }
+        }
+    }
+    None
+}
@@ -1,68 +1,105 @@
 #![allow(dead_code)]
 
 mod joltages;
+mod lights;
 
 use joltages::Joltages;
+use lights::BitSet;
 use std::collections::{HashMap, HashSet, VecDeque};
-use std::io::Result;
+use std::io::{Error, ErrorKind, Result};
 use std::path::Path;
 
 #[derive(Debug, Default, Clone)]
 struct Machine {
     led_count: usize,
+    // The u64 based fields are only filled in for up to 64 LEDs, see
+    // light_target and light_buttons for any number of them
     led_target: u64,
     buttons: Vec<u64>,
+    light_target: BitSet,
+    light_buttons: Vec<BitSet>,
     button_joltages: Vec<Joltages>,
     joltage_target: Joltages,
 }
 
-fn read_machines<P: AsRef<Path>>(path: P) -> Result<Vec<Machine>> {
-    Ok(std::fs::read_to_string(path)?
-        .lines()
-        .map(|line| {
-            let parts: Vec<&str> = line.split_whitespace().collect();
-            let mut machine = Machine::default();
-
-            for part in parts {
-                match part.chars().next().unwrap() {
-                    '[' => {
-                        machine.led_target = part[1..part.len() - 1].chars().enumerate().fold(
-                            0u64,
-                            |acc, (i, c)| {
-                                if c == '#' { acc | (1 << i) } else { acc }
-                            },
-                        );
-                        machine.led_count = part.len() - 2;
-                    }
-                    '(' => {
-                        let button = part[1..part.len() - 1]
-                            .split(',')
-                            .map(|s| s.parse::<usize>().unwrap())
-                            .fold(0u64, |acc, led_index| acc | (1 << led_index));
-                        machine.buttons.push(button);
-                        machine
-                            .button_joltages
-                            .push(Joltages::from_u64(button, machine.led_count));
-                    }
-                    '{' => {
-                        machine.joltage_target = Joltages::new(machine.led_count);
-                        part[1..part.len() - 1]
-                            .split(',')
-                            .map(|s| s.parse::<u64>().unwrap())
-                            .enumerate()
-                            .for_each(|(idx, j)| machine.joltage_target[idx] = j);
-                    }
-                    _ => {}
+fn read_machine(line: &str) -> Result<Machine> {
+    let invalid = |what: String| Error::new(ErrorKind::InvalidInput, what);
+    let mut machine = Machine::default();
+
+    for part in line.split_whitespace() {
+        match part.chars().next().unwrap() {
+            '[' => {
+                let lit = part[1..part.len() - 1]
+                    .chars()
+                    .enumerate()
+                    .filter(|(_, c)| *c == '#')
+                    .map(|(i, _)| i);
+                machine.led_count = part.len() - 2;
+                machine.light_target = BitSet::from_indices(machine.led_count, lit);
+                if machine.led_count <= 64 {
+                    machine.led_target = machine
+                        .light_target
+                        .ones()
+                        .fold(0, |acc, led_index| acc | (1 << led_index));
                 }
             }
+            '(' => {
+                let leds: Vec<usize> = part[1..part.len() - 1]
+                    .split(',')
+                    .map(|s| {
+                        s.parse::<usize>()
+                            .map_err(|_| invalid(format!("invalid button '{part}'")))
+                    })
+                    .collect::<Result<_>>()?;
+                if let Some(led) = leds.iter().find(|&&led| led >= machine.led_count) {
+                    return Err(invalid(format!(
+                        "button '{part}' toggles LED {led}, but there are only {}",
+                        machine.led_count
+                    )));
+                }
+                if machine.led_count <= 64 {
+                    let button = leds
+                        .iter()
+                        .fold(0u64, |acc, led_index| acc | (1 << led_index));
+                    machine.buttons.push(button);
+                    machine
+                        .button_joltages
+                        .push(Joltages::from_u64(button, machine.led_count));
+                }
+                machine
+                    .light_buttons
+                    .push(BitSet::from_indices(machine.led_count, leds));
+            }
+            '{' if machine.led_count <= 64 => {
+                machine.joltage_target = Joltages::new(machine.led_count);
+                part[1..part.len() - 1]
+                    .split(',')
+                    .map(|s| s.parse::<u64>().unwrap())
+                    .enumerate()
+                    .for_each(|(idx, j)| machine.joltage_target[idx] = j);
+            }
+            _ => {}
+        }
+    }
 
-            machine
-        })
-        .collect())
+    Ok(machine)
+}
+
+fn read_machines<P: AsRef<Path>>(path: P) -> Result<Vec<Machine>> {
+    std::fs::read_to_string(path)?
+        .lines()
+        .map(read_machine)
+        .collect()
 }
 
 fn button_combo(machine: &Machine) -> Option<usize> {
-    let led_mask = (1 << machine.led_count) - 1;
+    assert!(
+        machine.led_count <= 64,
+        "button_combo needs at most 64 LEDs"
+    );
+    let led_mask = 1u64
+        .checked_shl(machine.led_count as u32)
+        .map_or(u64::MAX, |bit| bit - 1);
 
     // Queue (led_state, last_button, depth)
     let mut queue: VecDeque<(u64, u64, usize)> = VecDeque::new();
@@ -94,6 +131,18 @@ fn button_combo(machine: &Machine) -> Option<usize> {
     None
 }
 
+/// Fewest buttons to press to light up the target, and which (by index)
+fn light_presses(machine: &Machine) -> Option<Vec<usize>> {
+    lights::solve(&machine.light_buttons, &machine.light_target)
+}
+
+fn light_combos(machines: &[Machine]) -> usize {
+    machines
+        .iter()
+        .map(|m| light_presses(m).map_or(0, |presses| presses.len()))
+        .sum()
+}
+
 #[derive(Debug, Clone)]
 struct State {
     joltage: Joltages,
@@ -194,6 +243,10 @@ fn count_buttons(
 }
 
 fn joltage_combo(machine: &Machine) -> Option<usize> {
+    assert!(
+        machine.led_count <= 64,
+        "joltage_combo needs at most 64 LEDs"
+    );
     let result = count_buttons(
         machine.joltage_target,
         &machine.button_joltages,
@@ -210,14 +263,65 @@ fn joltage_combos(machines: &[Machine]) -> usize {
     machines.iter().map(|m| joltage_combo(m).unwrap_or(0)).sum()
 }
 
-fn main() {
+fn main() -> Result<()> {
     println!("Run 'cargo test' instead ;)");
+    println!("Or: day_10 <file>");
+
+    let Some(path) = std::env::args().nth(1) else {
+        return Ok(());
+    };
+    for (idx, machine) in read_machines(path)?.iter().enumerate() {
+        match light_presses(machine) {
+            Some(presses) => println!(
+                "machine {}: {} presses, buttons {:?}",
+                idx + 1,
+                presses.len(),
+                presses
+            ),
+            None => println!("machine {}: impossible", idx + 1),
+        }
+    }
+    Ok(())
 }
 
 #[cfg(test)]
 mod tests {
     mod day10_factory {
         use super::super::*;
+        use utils::Rng;
+
+        // Lights the buttons turn on, pressing each of them once
+        fn press(machine: &Machine, presses: &[usize]) -> BitSet {
+            let mut lights = BitSet::new(machine.led_count);
+            presses
+                .iter()
+                .for_each(|&idx| lights.xor(&machine.light_buttons[idx]));
+            lights
+        }
+
+        fn random_machine(rng: &mut Rng, led_count: usize, button_count: usize) -> Machine {
+            let mut machine = Machine {
+                led_count,
+                ..Default::default()
+            };
+            for _ in 0..button_count {
+                let leds: Vec<usize> = (0..led_count).filter(|_| rng.range(0..3) == 0).collect();
+                if led_count <= 64 {
+                    machine
+                        .buttons
+                        .push(leds.iter().fold(0, |acc, led| acc | 1 << led));
+                }
+                machine
+                    .light_buttons
+                    .push(BitSet::from_indices(led_count, leds));
+            }
+            let lit: Vec<usize> = (0..led_count).filter(|_| rng.bool()).collect();
+            if led_count <= 64 {
+                machine.led_target = lit.iter().fold(0, |acc, led| acc | 1 << led);
+            }
+            machine.light_target = BitSet::from_indices(led_count, lit);
+            machine
+        }
 
         #[test]
         fn sample() {
@@ -226,11 +330,134 @@ mod tests {
             assert_eq!(joltage_combos(&machines), 33);
         }
 
+        #[test]
+        fn sample_lights() {
+            let machines = read_machines("data/sample.txt").unwrap();
+            assert_eq!(light_combos(&machines), 7);
+            let presses: Vec<Vec<usize>> =
+                machines.iter().map(|m| light_presses(m).unwrap()).collect();
+            assert_eq!(presses.iter().map(Vec::len).collect::<Vec<_>>(), [2, 3, 2]);
+            for (machine, presses) in machines.iter().zip(&presses) {
+                assert_eq!(press(machine, presses), machine.light_target);
+            }
+        }
+
+        #[test]
+        fn bitset() {
+            let mut bits = BitSet::from_indices(130, [0, 63, 64, 129]);
+            assert_eq!(bits.len(), 130);
+            assert_eq!(bits.count_ones(), 4);
+            assert!(bits.get(64) && !bits.get(65));
+            bits.set(63, false);
+            bits.xor(&BitSet::from_indices(130, [0, 1]));
+            assert_eq!(bits.ones().collect::<Vec<_>>(), [1, 64, 129]);
+            bits.xor(&bits.clone());
+            assert!(bits.is_zero());
+        }
+
+        #[test]
+        fn random_lights() {
+            let mut rng = Rng::new(10);
+            for _ in 0..300 {
+                let led_count = 1 + rng.index(10);
+                let button_count = 1 + rng.index(8);
+                let machine = random_machine(&mut rng, led_count, button_count);
+
+                let presses = light_presses(&machine);
+                if machine.light_target.is_zero() {
+                    // The search only ever reports a press or more
+                    assert_eq!(presses, Some(vec![]));
+                    continue;
+                }
+                assert_eq!(presses.as_ref().map(Vec::len), button_combo(&machine));
+                if let Some(presses) = presses {
+                    assert_eq!(press(&machine, &presses), machine.light_target);
+                }
+            }
+        }
+
+        #[test]
+        fn many_lights() {
+            let mut rng = Rng::new(100);
+            let mut machine = random_machine(&mut rng, 150, 20);
+            // Reachable by construction, with at most 5 presses
+            let chosen = [2, 3, 5, 11, 17];
+            machine.light_target = press(&machine, &chosen);
+
+            let presses = light_presses(&machine).unwrap();
+            assert!(presses.len() <= chosen.len());
+            assert_eq!(press(&machine, &presses), machine.light_target);
+
+            // A light no button reaches can't be turned on
+            machine.light_target.set(149, true);
+            machine
+                .light_buttons
+                .iter_mut()
+                .for_each(|button| button.set(149, false));
+            assert_eq!(light_presses(&machine), None);
+        }
+
+        #[test]
+        fn many_buttons() {
+            // Buttons toggling nothing, or the same as another, don't count
+            let mut machine = Machine {
+                led_count: 70,
+                light_target: BitSet::from_indices(70, [3, 69]),
+                light_buttons: vec![BitSet::new(70); 70],
+                ..Default::default()
+            };
+            assert_eq!(light_presses(&machine), None);
+            machine.light_buttons[40] = machine.light_target.clone();
+            machine.light_buttons[50] = machine.light_target.clone();
+            assert_eq!(light_presses(&machine), Some(vec![40]));
+
+            // Too many different buttons for a few lights to try every
+            // combination, so the light states are searched
+            let mut rng = Rng::new(40);
+            for _ in 0..20 {
+                let mut masks: Vec<usize> = (1..64).collect();
+                rng.shuffle(&mut masks);
+                let buttons: Vec<String> = masks[..40]
+                    .iter()
+                    .map(|mask| {
+                        let leds: Vec<String> = (0..6)
+                            .filter(|led| mask >> led & 1 == 1)
+                            .map(|led| led.to_string())
+                            .collect();
+                        format!("({})", leds.join(","))
+                    })
+                    .collect();
+                let lights: String = (0..6).map(|_| if rng.bool() { '#' } else { '.' }).collect();
+                let machine = read_machine(&format!("[{lights}] {}", buttons.join(" "))).unwrap();
+
+                let presses = light_presses(&machine).unwrap();
+                assert_eq!(press(&machine, &presses), machine.light_target);
+                if !machine.light_target.is_zero() {
+                    assert_eq!(Some(presses.len()), button_combo(&machine));
+                }
+            }
+        }
+
+        #[test]
+        fn parsing() {
+            assert!(read_machine("[.#.] (0,2) (1) {1,2,3}").is_ok());
+            assert!(read_machine("[.#.] (0,3) (1) {1,2,3}").is_err());
+            assert!(read_machine("[.#.] (0,x) {1,2,3}").is_err());
+
+            // Past 64 LEDs, only the bit sets are filled in
+            let lights = ".".repeat(69) + "#";
+            let machine = read_machine(&format!("[{lights}] (0,69) (0) {{1,2}}")).unwrap();
+            assert_eq!(machine.led_count, 70);
+            assert!(machine.buttons.is_empty() && machine.button_joltages.is_empty());
+            assert_eq!(light_presses(&machine), Some(vec![0, 1]));
+        }
+
         #[test]
         #[cfg(feature = "private")]
         fn input() {
             let machines = read_machines("data/input.txt").unwrap();
             assert_eq!(button_combos(&machines), 488);
+            assert_eq!(light_combos(&machines), 488);
             assert_eq!(joltage_combos(&machines), 18771);
         }
     }
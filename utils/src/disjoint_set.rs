@@ -0,0 +1,94 @@
+/// Union-find over the elements 0..len, with path compression and union by
+/// size. Sets are identified by their root element.
+#[derive(Debug, Clone)]
+pub struct DisjointSet {
+    parent: Vec<usize>,
+    // Only valid for roots
+    size: Vec<usize>,
+    components: usize,
+}
+
+impl DisjointSet {
+    /// Every element in a set of its own
+    pub fn new(len: usize) -> Self {
+        DisjointSet {
+            parent: (0..len).collect(),
+            size: vec![1; len],
+            components: len,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.parent.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.parent.is_empty()
+    }
+
+    /// Root of the set containing `element`
+    pub fn find(&mut self, element: usize) -> usize {
+        let mut root = element;
+        while self.parent[root] != root {
+            root = self.parent[root];
+        }
+
+        // Point everything on the way directly at the root
+        let mut current = element;
+        while self.parent[current] != root {
+            current = std::mem::replace(&mut self.parent[current], root);
+        }
+        root
+    }
+
+    /// Merges the sets of both elements, the smaller one into the larger one.
+    /// Returns false if they were already in the same set.
+    pub fn union(&mut self, first: usize, second: usize) -> bool {
+        let (mut first, mut second) = (self.find(first), self.find(second));
+        if first == second {
+            return false;
+        }
+        if self.size[first] < self.size[second] {
+            std::mem::swap(&mut first, &mut second);
+        }
+        self.parent[second] = first;
+        self.size[first] += self.size[second];
+        self.components -= 1;
+        true
+    }
+
+    pub fn connected(&mut self, first: usize, second: usize) -> bool {
+        self.find(first) == self.find(second)
+    }
+
+    /// Size of the set containing `element`
+    pub fn size(&mut self, element: usize) -> usize {
+        let root = self.find(element);
+        self.size[root]
+    }
+
+    /// Number of disjoint sets
+    pub fn components(&self) -> usize {
+        self.components
+    }
+
+    /// Sizes of all sets, in no particular order
+    pub fn sizes(&self) -> Vec<usize> {
+        (0..self.len())
+            .filter(|&element| self.parent[element] == element)
+            .map(|root| self.size[root])
+            .collect()
+    }
+
+    /// Sizes of the (up to) k largest sets, largest first
+    pub fn largest(&self, k: usize) -> Vec<usize> {
+        let mut sizes = self.sizes();
+        let k = k.min(sizes.len());
+        if k < sizes.len() {
+            sizes.select_nth_unstable_by(k, |a, b| b.cmp(a));
+            sizes.truncate(k);
+        }
+        sizes.sort_unstable_by(|a, b| b.cmp(a));
+        sizes
+    }
+}
@@ -1,6 +1,7 @@
 use std::io::{Error, ErrorKind, Result};
 use std::path::Path;
 
+#[derive(Clone)]
 pub struct Grid<T> {
     data: Vec<T>,
     width: usize,
@@ -90,6 +91,19 @@ impl<T> Grid<T> {
             .collect()
     }
 
+    pub fn orthogonal_neighbors(&self, x: usize, y: usize) -> Vec<(usize, usize)> {
+        let directions: [(usize, usize); 4] = [
+            (x, y.wrapping_sub(1)),
+            (x.wrapping_sub(1), y),
+            (x.wrapping_add(1), y),
+            (x, y.wrapping_add(1)),
+        ];
+        directions
+            .into_iter()
+            .filter(|(x, y)| *x < self.width && *y < self.height)
+            .collect()
+    }
+
     pub fn coordinates(&self) -> impl Iterator<Item = (usize, usize)> {
         (0..self.height).flat_map(|y| (0..self.width).map(move |x| (x, y)))
     }
@@ -109,7 +123,7 @@ impl<T> Grid<T> {
             .iter()
             .enumerate()
             .find(|(_, value)| **value == what)
-            .map(|(idx, _)| (idx % self.width, idx / self.height))
+            .map(|(idx, _)| (idx % self.width, idx / self.width))
     }
 }
 
@@ -10,4 +10,10 @@ pub use line::Line;
 mod rectangle;
 pub use rectangle::Rectangle;
 
+mod rng;
+pub use rng::Rng;
+
+mod disjoint_set;
+pub use disjoint_set::DisjointSet;
+
 pub mod math;
@@ -57,8 +57,26 @@ impl Rectangle {
         ]
     }
 
+    /// Number of columns, including both edges
+    pub fn width(&self) -> usize {
+        self.bottom_right.x - self.top_left.x + 1
+    }
+
+    /// Number of rows, including both edges
+    pub fn height(&self) -> usize {
+        self.bottom_right.y - self.top_left.y + 1
+    }
+
     pub fn area(&self) -> usize {
-        (self.bottom_right.x - self.top_left.x + 1) * (self.bottom_right.y - self.top_left.y + 1)
+        self.width() * self.height()
+    }
+
+    pub fn perimeter(&self) -> usize {
+        2 * (self.width() + self.height())
+    }
+
+    pub fn is_square(&self) -> bool {
+        self.width() == self.height()
     }
 
     pub fn is_degenerate(&self) -> bool {